@@ -0,0 +1,311 @@
+extern crate punchcards;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use std::env;
+use std::process;
+use std::collections::BTreeMap;
+use punchcards::state::{self, State};
+use punchcards::bot::{Action, Bot, GreedyBot, RandomBot};
+use punchcards::card::Card;
+use punchcards::script;
+use punchcards::content::Content;
+use punchcards::{ExternalEvent, GameplayEvent};
+
+const DEFAULT_GAMES: usize = 1000;
+const DEFAULT_MAX_TURNS: usize = 500;
+const STUCK_WAITS: usize = 20;
+
+const USAGE: &'static str = "\
+usage: punchcards_balance [options]
+  --games N          number of games to play (default 1000)
+  --seed N           seed of the first game, later games count up from it (default 0)
+  --deck CARDS       starting deck, e.g. punch=2,move=2,shoot=7 (default: the standard deck)
+  --hand-size N      cards in hand (default: the game's hand size)
+  --bot NAME         greedy or random (default greedy)
+  --max-turns N      give up on a game after this many turns (default 500)
+  --format FORMAT    csv or json (default csv)";
+
+#[derive(Clone, Copy)]
+enum BotKind {
+    Greedy,
+    Random,
+}
+
+impl BotKind {
+    fn name(self) -> &'static str {
+        match self {
+            BotKind::Greedy => "greedy",
+            BotKind::Random => "random",
+        }
+    }
+
+    fn build(self, seed: usize) -> Box<Bot> {
+        match self {
+            BotKind::Greedy => Box::new(GreedyBot),
+            BotKind::Random => Box::new(RandomBot::new(seed)),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Config {
+    games: usize,
+    seed: usize,
+    deck: Vec<Card>,
+    hand_size: usize,
+    bot: BotKind,
+    max_turns: usize,
+    format: Format,
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got \"{}\"", flag, value))
+}
+
+fn parse_deck(value: &str) -> Result<Vec<Card>, String> {
+    let mut deck = Vec::new();
+    for entry in value.split(',') {
+        let mut parts = entry.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let count = match parts.next() {
+            Some(count) => parse_number("--deck", count.trim())?,
+            None => 1,
+        };
+        let card = match name {
+            "punch" => Card::Punch,
+            "move" => Card::Move,
+            "shoot" => Card::Shoot,
+            "jab" => Card::Scripted(script::JAB),
+            other => return Err(format!("unknown card \"{}\"", other)),
+        };
+        for _ in 0..count {
+            deck.push(card);
+        }
+    }
+    Ok(deck)
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Config, String> {
+    let mut config = Config {
+        games: DEFAULT_GAMES,
+        seed: 0,
+        deck: state::standard_deck(),
+        hand_size: state::INITIAL_HAND_SIZE,
+        bot: BotKind::Greedy,
+        max_turns: DEFAULT_MAX_TURNS,
+        format: Format::Csv,
+    };
+    while let Some(flag) = args.next() {
+        let value = args.next()
+            .ok_or_else(|| format!("{} expects a value", flag))?;
+        match flag.as_str() {
+            "--games" => config.games = parse_number(&flag, &value)?,
+            "--seed" => config.seed = parse_number(&flag, &value)?,
+            "--deck" => config.deck = parse_deck(&value)?,
+            "--hand-size" => config.hand_size = parse_number(&flag, &value)?,
+            "--max-turns" => config.max_turns = parse_number(&flag, &value)?,
+            "--bot" => {
+                config.bot = match value.as_str() {
+                    "greedy" => BotKind::Greedy,
+                    "random" => BotKind::Random,
+                    other => return Err(format!("unknown bot \"{}\"", other)),
+                }
+            }
+            "--format" => {
+                config.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format \"{}\"", other)),
+                }
+            }
+            other => return Err(format!("unknown option \"{}\"", other)),
+        }
+    }
+    if config.hand_size == 0 {
+        return Err("--hand-size must be at least 1".to_string());
+    }
+    if config.deck.is_empty() {
+        return Err("--deck must contain at least one card".to_string());
+    }
+    Ok(config)
+}
+
+#[derive(Clone, Copy)]
+enum Outcome {
+    Victory,
+    OutOfCards,
+    Stuck,
+    TurnLimit,
+}
+
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Victory => "victory",
+            Outcome::OutOfCards => "out_of_cards",
+            Outcome::Stuck => "stuck",
+            Outcome::TurnLimit => "turn_limit",
+        }
+    }
+}
+
+struct GameResult {
+    depth: usize,
+    turns: usize,
+    cards_picked_up: usize,
+    outcome: Outcome,
+}
+
+fn play(config: &Config, seed: usize) -> GameResult {
+    let mut state = State::with_deck(
+        seed,
+        config.deck.clone(),
+        config.hand_size,
+        Content::builtin(),
+    );
+    let mut bot = config.bot.build(seed);
+
+    let mut depth = 0;
+    let mut cards_picked_up = 0;
+    let mut waits = 0;
+
+    let outcome = loop {
+        if state.turn_count() as usize >= config.max_turns {
+            break Outcome::TurnLimit;
+        }
+
+        let action = bot.act(&state);
+        let event = state.headless_turn(action.inputs());
+
+        cards_picked_up += state
+            .gameplay_events()
+            .iter()
+            .filter(|event| match **event {
+                GameplayEvent::CardTaken(_) => true,
+                _ => false,
+            })
+            .count();
+        depth = depth.max(state.depth());
+
+        match action {
+            Action::Wait => {
+                // the bot had nothing it could play
+                waits += 1;
+                if waits >= STUCK_WAITS {
+                    break Outcome::Stuck;
+                }
+            }
+            Action::Play(..) => waits = 0,
+        }
+
+        match event {
+            Some(ExternalEvent::GameOver) => break Outcome::OutOfCards,
+            Some(ExternalEvent::Victory) => break Outcome::Victory,
+            _ => (),
+        }
+    };
+
+    GameResult {
+        depth,
+        turns: state.turn_count() as usize,
+        cards_picked_up,
+        outcome,
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    games: usize,
+    bot: &'static str,
+    deck_size: usize,
+    hand_size: usize,
+    average_turns: f64,
+    average_cards_picked_up: f64,
+    depth_distribution: BTreeMap<usize, usize>,
+    outcomes: BTreeMap<&'static str, usize>,
+}
+
+impl Report {
+    fn new(config: &Config, results: &[GameResult]) -> Self {
+        let games = results.len();
+        let average = |total: usize| {
+            if games == 0 {
+                0.0
+            } else {
+                total as f64 / games as f64
+            }
+        };
+        let mut depth_distribution = BTreeMap::new();
+        let mut outcomes = BTreeMap::new();
+        for result in results {
+            *depth_distribution.entry(result.depth).or_insert(0) += 1;
+            *outcomes.entry(result.outcome.name()).or_insert(0) += 1;
+        }
+        Self {
+            games,
+            bot: config.bot.name(),
+            deck_size: config.deck.len(),
+            hand_size: config.hand_size,
+            average_turns: average(results.iter().map(|result| result.turns).sum()),
+            average_cards_picked_up: average(
+                results.iter().map(|result| result.cards_picked_up).sum(),
+            ),
+            depth_distribution,
+            outcomes,
+        }
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("metric,key,value\n");
+        csv.push_str(&format!("games,,{}\n", self.games));
+        csv.push_str(&format!("bot,,{}\n", self.bot));
+        csv.push_str(&format!("deck_size,,{}\n", self.deck_size));
+        csv.push_str(&format!("hand_size,,{}\n", self.hand_size));
+        csv.push_str(&format!("average_turns,,{:.2}\n", self.average_turns));
+        csv.push_str(&format!(
+            "average_cards_picked_up,,{:.2}\n",
+            self.average_cards_picked_up
+        ));
+        for (depth, count) in self.depth_distribution.iter() {
+            csv.push_str(&format!("depth,{},{}\n", depth, count));
+        }
+        for (outcome, count) in self.outcomes.iter() {
+            csv.push_str(&format!("outcome,{},{}\n", outcome, count));
+        }
+        csv
+    }
+}
+
+fn main() {
+    let config = match parse_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(1);
+        }
+    };
+
+    let results = (0..config.games)
+        .map(|game| play(&config, config.seed.wrapping_add(game)))
+        .collect::<Vec<_>>();
+
+    let report = Report::new(&config, &results);
+
+    match config.format {
+        Format::Csv => print!("{}", report.csv()),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("Failed to serialize report")
+        ),
+    }
+}
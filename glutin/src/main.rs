@@ -4,30 +4,83 @@ extern crate prototty_glutin;
 extern crate punchcards_prototty;
 extern crate rand;
 
+use std::env;
+use std::process;
 use std::time::Instant;
 use rand::Rng;
 use prototty::Renderer;
 use prototty_file_storage::FileStorage;
 use prototty_glutin::*;
 use punchcards_prototty::*;
+use punchcards_prototty::cli::{self, CommandLine};
+use punchcards_prototty::locations::Locations;
+use punchcards_prototty::session::Session;
 
-const USER_DIR: &'static str = "user";
+const WINDOW_WIDTH: u32 = 960;
+const WINDOW_HEIGHT: u32 = 720;
+const CELL_SIZE: f32 = 32.0;
+const UNDERLINE_POSITION: f32 = 28.0;
+const UNDERLINE_WIDTH: f32 = 2.0;
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\nusage: punchcards_glutin [options]\n{}", message, cli::OPTIONS_HELP);
+    process::exit(1);
+}
+
+fn command_line() -> CommandLine {
+    let command_line =
+        CommandLine::parse(env::args().skip(1)).unwrap_or_else(|message| usage_error(&message));
+    if let Some(arg) = command_line.rest.first() {
+        usage_error(&format!("Unknown option \"{}\"", arg));
+    }
+    command_line
+}
 
 fn main() {
+    let command_line = command_line();
+
+    let (window_width, window_height) = command_line
+        .window_size
+        .unwrap_or((WINDOW_WIDTH, WINDOW_HEIGHT));
+    let font_scale = command_line.font_scale.unwrap_or(1.0);
+    let cell_size = ((CELL_SIZE * font_scale) as u32).max(1);
+
     let mut context = ContextBuilder::new_with_font(include_bytes!("fonts/PxPlus_IBM_CGAthin.ttf"))
         .with_bold_font(include_bytes!("fonts/PxPlus_IBM_CGA.ttf"))
-        .with_window_dimensions(960, 720)
-        .with_font_scale(32.0, 32.0)
-        .with_cell_dimensions(32, 32)
-        .with_underline_position(28)
-        .with_underline_width(2)
+        .with_window_dimensions(window_width, window_height)
+        .with_font_scale(CELL_SIZE * font_scale, CELL_SIZE * font_scale)
+        .with_cell_dimensions(cell_size, cell_size)
+        .with_underline_position((UNDERLINE_POSITION * font_scale) as u32)
+        .with_underline_width(((UNDERLINE_WIDTH * font_scale) as u32).max(1))
         .with_max_grid_size(30, 20)
         .build()
         .unwrap();
 
-    let storage = FileStorage::next_to_exe(USER_DIR, true).expect("Failed to find user dir");
+    let mut session =
+        Session::new(&command_line, rand::thread_rng().gen()).expect("Failed to read replay");
 
-    let mut app = App::new(Frontend::Glutin, storage, rand::thread_rng().gen());
+    let mut app = match session.scratch_dir() {
+        Some(dir) => {
+            let storage = FileStorage::new(dir, true).expect("Failed to create scratch dir");
+            App::new(Frontend::Glutin, storage, session.seed())
+        }
+        None => {
+            let locations =
+                Locations::resolve(command_line.user_dir.as_ref().map(String::as_str))
+                    .expect("Failed to find user dir");
+            let storage =
+                FileStorage::new(&locations.data, true).expect("Failed to open user dir");
+            let settings_storage =
+                FileStorage::new(&locations.config, true).expect("Failed to open config dir");
+            App::with_settings_storage(
+                Frontend::Glutin,
+                storage,
+                Some(settings_storage),
+                session.seed(),
+            )
+        }
+    };
+    session.start(&mut app, context.size());
 
     let mut input_buffer = Vec::with_capacity(64);
 
@@ -54,10 +107,18 @@ fn main() {
             input_buffer.push(input);
         });
 
-        if let Some(control_flow) = app.tick(input_buffer.drain(..), duration) {
+        let live_inputs = input_buffer.drain(..).collect::<Vec<_>>();
+        let (inputs, duration) = match session.tick(live_inputs, duration) {
+            Some(tick) => tick,
+            None => break,
+        };
+
+        if let Some(control_flow) = app.tick(inputs, duration) {
             match control_flow {
                 ControlFlow::Quit => running = false,
             }
         }
     }
+
+    session.finish().expect("Failed to write replay");
 }
@@ -0,0 +1,218 @@
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+use std::time::Duration;
+use prototty::*;
+
+const DEFAULT_FOREGROUND: Rgb24 = colours::WHITE;
+const DEFAULT_BACKGROUND: Rgb24 = colours::BLACK;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    underline: bool,
+    foreground: Rgb24,
+    background: Rgb24,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CastCell {
+    character: char,
+    style: Style,
+    depth: i32,
+}
+
+impl CastCell {
+    fn blank() -> Self {
+        Self {
+            character: ' ',
+            style: Style {
+                bold: false,
+                underline: false,
+                foreground: DEFAULT_FOREGROUND,
+                background: DEFAULT_BACKGROUND,
+            },
+            depth: i32::min_value(),
+        }
+    }
+
+    pub fn character(&self) -> char {
+        self.character
+    }
+    pub fn bold(&self) -> bool {
+        self.style.bold
+    }
+    pub fn underline(&self) -> bool {
+        self.style.underline
+    }
+    pub fn foreground(&self) -> Rgb24 {
+        self.style.foreground
+    }
+    pub fn background(&self) -> Rgb24 {
+        self.style.background
+    }
+}
+
+impl ViewCell for CastCell {
+    fn set_character(&mut self, character: char) {
+        self.character = character;
+    }
+    fn set_bold(&mut self, bold: bool) {
+        self.style.bold = bold;
+    }
+    fn set_underline(&mut self, underline: bool) {
+        self.style.underline = underline;
+    }
+    fn set_foreground_colour(&mut self, colour: Rgb24) {
+        self.style.foreground = colour;
+    }
+    fn set_background_colour(&mut self, colour: Rgb24) {
+        self.style.background = colour;
+    }
+}
+
+// an in-memory screen that views can be rendered into without a terminal
+pub struct CastGrid {
+    size: Size,
+    cells: Vec<CastCell>,
+}
+
+impl CastGrid {
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            cells: vec![CastCell::blank(); (size.width() * size.height()) as usize],
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn get(&self, coord: Coord) -> Option<&CastCell> {
+        if coord.x < 0 || coord.y < 0 || coord.x as u32 >= self.size.width()
+            || coord.y as u32 >= self.size.height()
+        {
+            return None;
+        }
+        self.cells
+            .get((coord.y as u32 * self.size.width() + coord.x as u32) as usize)
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = CastCell::blank();
+        }
+    }
+
+    pub fn render<T, V: View<T>>(&mut self, view: &mut V, data: &T) {
+        self.clear();
+        view.view(data, Coord::new(0, 0), 0, self);
+    }
+
+    // the whole screen as ansi escapes, drawn from the top left
+    pub fn ansi(&self) -> String {
+        let mut ansi = String::from("\x1b[H");
+        let mut current = None;
+        for (y, row) in self.cells.chunks(self.size.width() as usize).enumerate() {
+            if y > 0 {
+                ansi.push_str("\r\n");
+            }
+            for cell in row {
+                if current != Some(cell.style) {
+                    let style = cell.style;
+                    write!(
+                        ansi,
+                        "\x1b[0;38;2;{};{};{};48;2;{};{};{}",
+                        style.foreground.red,
+                        style.foreground.green,
+                        style.foreground.blue,
+                        style.background.red,
+                        style.background.green,
+                        style.background.blue,
+                    ).unwrap();
+                    if style.bold {
+                        ansi.push_str(";1");
+                    }
+                    if style.underline {
+                        ansi.push_str(";4");
+                    }
+                    ansi.push('m');
+                    current = Some(style);
+                }
+                ansi.push(cell.character);
+            }
+        }
+        ansi.push_str("\x1b[0m");
+        ansi
+    }
+}
+
+impl ViewGrid for CastGrid {
+    type Cell = CastCell;
+    fn get_mut(&mut self, coord: Coord, depth: i32) -> Option<&mut CastCell> {
+        if coord.x < 0 || coord.y < 0 || coord.x as u32 >= self.size.width()
+            || coord.y as u32 >= self.size.height()
+        {
+            return None;
+        }
+        let index = (coord.y as u32 * self.size.width() + coord.x as u32) as usize;
+        let cell = &mut self.cells[index];
+        if depth >= cell.depth {
+            cell.depth = depth;
+            Some(cell)
+        } else {
+            None
+        }
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from("\"");
+    for ch in string.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            ch if (ch as u32) < 0x20 => write!(json, "\\u{:04x}", ch as u32).unwrap(),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// writes an asciicast v2 file, with an event each time the screen changes
+pub struct CastWriter<W: Write> {
+    output: W,
+    grid: CastGrid,
+    last_frame: String,
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(mut output: W, size: Size) -> io::Result<Self> {
+        writeln!(
+            output,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+            size.width(),
+            size.height()
+        )?;
+        Ok(Self {
+            output,
+            grid: CastGrid::new(size),
+            last_frame: String::new(),
+        })
+    }
+
+    pub fn frame<T, V: View<T>>(&mut self, view: &mut V, data: &T, time: Duration) -> io::Result<()> {
+        self.grid.render(view, data);
+        let frame = self.grid.ansi();
+        if frame == self.last_frame {
+            return Ok(());
+        }
+        let seconds = time.as_secs() as f64 + time.subsec_nanos() as f64 / 1_000_000_000.0;
+        writeln!(self.output, "[{:.3}, \"o\", {}]", seconds, json_string(&frame))?;
+        self.last_frame = frame;
+        Ok(())
+    }
+}
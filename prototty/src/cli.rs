@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+pub const OPTIONS_HELP: &'static str = "\
+  --seed N               seed for the random number generator
+  --user-dir DIR         keep saves, packs, levels and settings here instead
+  --new-game             skip the menu and start a new game
+  --record-replay FILE   record inputs to a replay file (needs --new-game)
+  --play-replay FILE     play back a replay, then hand control to the player
+  --window-size WxH      window size in pixels (glutin only)
+  --font-scale N         size of text relative to the default (glutin only)";
+
+// options understood by every native frontend; anything else is left for the frontend
+#[derive(Debug, Clone, Default)]
+pub struct CommandLine {
+    pub seed: Option<usize>,
+    pub user_dir: Option<String>,
+    pub new_game: bool,
+    pub record_replay: Option<String>,
+    pub play_replay: Option<String>,
+    pub window_size: Option<(u32, u32)>,
+    pub font_scale: Option<f32>,
+    pub rest: Vec<String>,
+}
+
+fn value<I: Iterator<Item = String>>(flag: &str, args: &mut I) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn number<T: FromStr, I: Iterator<Item = String>>(flag: &str, args: &mut I) -> Result<T, String> {
+    let value = value(flag, args)?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got \"{}\"", flag, value))
+}
+
+fn window_size(value: &str) -> Result<(u32, u32), String> {
+    let mut parts = value.splitn(2, 'x');
+    let width = parts.next().and_then(|part| part.parse().ok());
+    let height = parts.next().and_then(|part| part.parse().ok());
+    match (width, height) {
+        (Some(0), _) | (_, Some(0)) => {
+            Err(format!("--window-size can't be zero, got \"{}\"", value))
+        }
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(format!("--window-size expects WIDTHxHEIGHT, got \"{}\"", value)),
+    }
+}
+
+impl CommandLine {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut command_line = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => command_line.seed = Some(number(&arg, &mut args)?),
+                "--user-dir" => command_line.user_dir = Some(value(&arg, &mut args)?),
+                "--new-game" => command_line.new_game = true,
+                "--record-replay" => command_line.record_replay = Some(value(&arg, &mut args)?),
+                "--play-replay" => command_line.play_replay = Some(value(&arg, &mut args)?),
+                "--window-size" => {
+                    command_line.window_size = Some(window_size(&value(&arg, &mut args)?)?)
+                }
+                "--font-scale" => {
+                    let scale: f32 = number(&arg, &mut args)?;
+                    if scale <= 0.0 {
+                        return Err("--font-scale must be positive".to_string());
+                    }
+                    command_line.font_scale = Some(scale);
+                }
+                _ => command_line.rest.push(arg),
+            }
+        }
+        if command_line.record_replay.is_some() && command_line.play_replay.is_some() {
+            return Err("can't record and play a replay at the same time".to_string());
+        }
+        // a replay doesn't hold the save it started from, so it must start from a new game
+        if command_line.record_replay.is_some() && !command_line.new_game {
+            return Err("--record-replay needs --new-game".to_string());
+        }
+        Ok(command_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CommandLine, String> {
+        CommandLine::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn window_size() {
+        let command_line = parse(&["--window-size", "640x480"]).unwrap();
+        assert_eq!(command_line.window_size, Some((640, 480)));
+        assert!(parse(&["--window-size", "640"]).is_err());
+        assert!(parse(&["--window-size", "wide"]).is_err());
+    }
+
+    #[test]
+    fn window_size_rejects_zero() {
+        assert!(parse(&["--window-size", "0x0"]).is_err());
+        assert!(parse(&["--window-size", "640x0"]).is_err());
+        assert!(parse(&["--window-size", "0x480"]).is_err());
+    }
+
+    #[test]
+    fn recording_a_replay_needs_a_new_game() {
+        assert!(parse(&["--record-replay", "run.json"]).is_err());
+        assert!(parse(&["--record-replay", "run.json", "--new-game"]).is_ok());
+    }
+
+    #[test]
+    fn unknown_arguments_are_left_for_the_frontend() {
+        let command_line = parse(&["--seed", "4", "--serve", "42"]).unwrap();
+        assert_eq!(command_line.seed, Some(4));
+        assert_eq!(command_line.rest, vec!["--serve".to_string(), "42".to_string()]);
+    }
+}
@@ -0,0 +1,344 @@
+use std::cell::Cell;
+use std::fmt::Write;
+use prototty::*;
+use prototty::Input as ProtottyInput;
+use prototty::inputs as prototty_inputs;
+use punchcards::content::Content;
+use punchcards::{validate_level, Arrival, Exit, MAX_HEIGHT, MAX_WIDTH};
+use punchcards::tile::Tile;
+use punchcards::tile_info::TileInfo;
+use super::view_tile;
+use settings::Glyphs;
+
+const MIN_SIZE: u32 = 3;
+const INITIAL_WIDTH: u32 = 10;
+const INITIAL_HEIGHT: u32 = 8;
+const CURSOR_DEPTH: i32 = 32;
+const PANEL_PADDING: i32 = 1;
+
+const BRUSHES: &'static [char] = &[
+    '#', '.', '@', '>', '<', 'm', 'j', '0', '1', '2', '3', '4', 'E', 'C',
+];
+
+pub enum EditorAction {
+    Playtest(Vec<String>),
+    Save,
+    Load,
+    Exit,
+}
+
+pub struct Editor {
+    rows: Vec<Vec<char>>,
+    cursor: Coord,
+    brush: usize,
+    pub status: Option<String>,
+    // where the grid was last drawn on screen, so clicks can be mapped onto it
+    grid_offset: Cell<Coord>,
+}
+
+fn glyph_tile(glyph: char, content: &Content) -> Option<Tile> {
+    let tile = match glyph {
+        '#' => Tile::Wall,
+        '.' => Tile::Floor,
+        '@' => Tile::Player,
+        '>' => Tile::Stairs,
+        '<' => Tile::UpStairs,
+        'm' => Tile::CardMove,
+        'j' => Tile::CardSpecial,
+        'E' => Tile::EliteRobot,
+        '0' => Tile::TargetDummy,
+        '1' => Tile::SmallRobot,
+        '2' => Tile::GunRobot,
+        '3' => Tile::Guard,
+        '4' => Tile::Skirmisher,
+        'C' => Tile::Champion(0),
+        other => return content.enemy_by_glyph(other).map(Tile::PackEnemy),
+    };
+    Some(tile)
+}
+
+fn brushes(content: &Content) -> Vec<char> {
+    BRUSHES
+        .iter()
+        .cloned()
+        .chain(content.enemies().iter().map(|enemy| enemy.glyph))
+        .collect()
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        let mut editor = Self {
+            rows: Vec::new(),
+            cursor: Coord::new(1, 1),
+            brush: 0,
+            status: None,
+            grid_offset: Cell::new(Coord::new(0, 0)),
+        };
+        editor.resize(INITIAL_WIDTH, INITIAL_HEIGHT);
+        editor
+    }
+
+    fn width(&self) -> u32 {
+        self.rows[0].len() as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.rows.len() as u32
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        let width = width.max(MIN_SIZE).min(MAX_WIDTH) as usize;
+        let height = height.max(MIN_SIZE).min(MAX_HEIGHT) as usize;
+        // the old border is cleared first, so growing the level doesn't leave it inside
+        let old_height = self.rows.len();
+        for (y, row) in self.rows.iter_mut().enumerate() {
+            let old_width = row.len();
+            for (x, cell) in row.iter_mut().enumerate() {
+                if x == old_width - 1 || y == old_height - 1 {
+                    *cell = '.';
+                }
+            }
+        }
+        self.rows.resize(height, Vec::new());
+        for (y, row) in self.rows.iter_mut().enumerate() {
+            row.resize(width, '.');
+            // keep a wall around the edge so levels are closed by default
+            for (x, cell) in row.iter_mut().enumerate() {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    *cell = '#';
+                }
+            }
+        }
+        self.cursor = Coord::new(
+            self.cursor.x.min(width as i32 - 1),
+            self.cursor.y.min(height as i32 - 1),
+        );
+    }
+
+    fn paint(&mut self, coord: Coord, content: &Content) {
+        if coord.x < 0 || coord.y < 0 {
+            return;
+        }
+        let glyph = brushes(content)[self.brush];
+        if let Some(cell) = self.rows
+            .get_mut(coord.y as usize)
+            .and_then(|row| row.get_mut(coord.x as usize))
+        {
+            *cell = glyph;
+        }
+    }
+
+    fn in_grid(&self, coord: Coord) -> bool {
+        coord.x >= 0 && coord.y >= 0 && (coord.x as u32) < self.width()
+            && (coord.y as u32) < self.height()
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let next = self.cursor + Coord::new(dx, dy);
+        if self.in_grid(next) {
+            self.cursor = next;
+        }
+    }
+
+    pub fn to_strings(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|row| row.iter().collect())
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in self.to_strings() {
+            text.push_str(&row);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn load_text(&mut self, text: &str) {
+        let rows: Vec<Vec<char>> = text.lines()
+            .map(str::trim_right)
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().collect())
+            .collect();
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if rows.len() < MIN_SIZE as usize || width < MIN_SIZE as usize
+            || rows.len() > MAX_HEIGHT as usize || width > MAX_WIDTH as usize
+            || rows.iter().any(|row| row.len() != width)
+        {
+            self.status = Some("Saved level is invalid".to_string());
+            return;
+        }
+        self.rows = rows;
+        self.cursor = Coord::new(1, 1);
+        self.status = Some("Loaded".to_string());
+    }
+
+    pub fn tick<I>(&mut self, inputs: I, content: &Content) -> Option<EditorAction>
+    where
+        I: IntoIterator<Item = ProtottyInput>,
+    {
+        let num_brushes = brushes(content).len();
+        for input in inputs {
+            match input {
+                ProtottyInput::Up => self.move_cursor(0, -1),
+                ProtottyInput::Down => self.move_cursor(0, 1),
+                ProtottyInput::Left => self.move_cursor(-1, 0),
+                ProtottyInput::Right => self.move_cursor(1, 0),
+                ProtottyInput::Char(' ') => {
+                    let cursor = self.cursor;
+                    self.paint(cursor, content);
+                }
+                ProtottyInput::MousePress { coord, .. } => {
+                    let coord = coord - self.grid_offset.get();
+                    if self.in_grid(coord) {
+                        self.cursor = coord;
+                        self.paint(coord, content);
+                    }
+                }
+                ProtottyInput::Char(']') => self.brush = (self.brush + 1) % num_brushes,
+                ProtottyInput::Char('[') => {
+                    self.brush = (self.brush + num_brushes - 1) % num_brushes
+                }
+                ProtottyInput::Char('H') => {
+                    let (width, height) = (self.width() - 1, self.height());
+                    self.resize(width, height);
+                }
+                ProtottyInput::Char('L') => {
+                    let (width, height) = (self.width() + 1, self.height());
+                    self.resize(width, height);
+                }
+                ProtottyInput::Char('K') => {
+                    let (width, height) = (self.width(), self.height() - 1);
+                    self.resize(width, height);
+                }
+                ProtottyInput::Char('J') => {
+                    let (width, height) = (self.width(), self.height() + 1);
+                    self.resize(width, height);
+                }
+                ProtottyInput::Char('p') => {
+                    let rows = self.to_strings();
+                    match validate_level(&rows, Arrival::Player, Exit::Optional, content) {
+                        Ok(()) => {
+                            self.status = None;
+                            return Some(EditorAction::Playtest(rows));
+                        }
+                        Err(error) => self.status = Some(error.to_string()),
+                    }
+                }
+                ProtottyInput::Char('s') => return Some(EditorAction::Save),
+                ProtottyInput::Char('o') => return Some(EditorAction::Load),
+                prototty_inputs::ESCAPE => return Some(EditorAction::Exit),
+                _ => (),
+            }
+        }
+        None
+    }
+
+    pub fn view<G: ViewGrid>(
+        &self,
+        content: &Content,
+        glyphs: Glyphs,
+        scratch: &mut String,
+        offset: Coord,
+        depth: i32,
+        grid: &mut G,
+    ) {
+        self.grid_offset.set(offset);
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, &glyph) in row.iter().enumerate() {
+                let coord = offset + Coord::new(x as i32, y as i32);
+                if let Some(tile) = glyph_tile(glyph, content) {
+                    if let Some(cell) = grid.get_mut(coord, depth) {
+                        view_tile(TileInfo::new(tile, depth), content, glyphs, cell);
+                    }
+                }
+            }
+        }
+
+        if let Some(cell) = grid.get_mut(offset + self.cursor, CURSOR_DEPTH + depth) {
+            cell.set_background_colour(colours::BLUE);
+        }
+
+        let panel = offset + Coord::new(self.width() as i32 + PANEL_PADDING, 0);
+        let brush = brushes(content)[self.brush];
+        write!(scratch, "Brush: {}", brush).unwrap();
+        StringView.view(&*scratch, panel, depth, grid);
+        scratch.clear();
+        write!(scratch, "Size: {}x{}", self.width(), self.height()).unwrap();
+        StringView.view(&*scratch, panel + Coord::new(0, 1), depth, grid);
+        scratch.clear();
+
+        let help = [
+            "[ ] brush",
+            "space paint",
+            "HJKL resize",
+            "p play",
+            "s save",
+            "o load",
+            "esc menu",
+        ];
+        for (i, line) in help.iter().enumerate() {
+            StringView.view(line, panel + Coord::new(0, i as i32 + 3), depth, grid);
+        }
+
+        if let Some(ref status) = self.status {
+            StringView.view(
+                status,
+                offset + Coord::new(0, self.height() as i32 + PANEL_PADDING),
+                depth,
+                grid,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walls_only_on_the_border(editor: &Editor) -> bool {
+        let (width, height) = (editor.width() as usize, editor.height() as usize);
+        editor.rows.iter().enumerate().all(|(y, row)| {
+            row.iter().enumerate().all(|(x, &cell)| {
+                let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                (cell == '#') == border
+            })
+        })
+    }
+
+    #[test]
+    fn growing_clears_the_old_border() {
+        let mut editor = Editor::new();
+        editor.resize(INITIAL_WIDTH + 2, INITIAL_HEIGHT + 3);
+        assert_eq!(editor.width(), INITIAL_WIDTH + 2);
+        assert_eq!(editor.height(), INITIAL_HEIGHT + 3);
+        assert!(walls_only_on_the_border(&editor));
+    }
+
+    #[test]
+    fn shrinking_keeps_a_border() {
+        let mut editor = Editor::new();
+        editor.resize(MIN_SIZE, MIN_SIZE + 1);
+        assert!(walls_only_on_the_border(&editor));
+        editor.resize(MIN_SIZE - 1, 0);
+        assert_eq!((editor.width(), editor.height()), (MIN_SIZE, MIN_SIZE));
+    }
+
+    #[test]
+    fn resize_keys_arent_brushes() {
+        let content = Content::builtin();
+        let mut editor = Editor::new();
+        let keys = ['H', 'J', 'K', 'L'];
+        for key in keys.iter() {
+            assert!(!brushes(&content).contains(key));
+        }
+        editor.tick(vec![ProtottyInput::Char('J'), ProtottyInput::Char('L')], &content);
+        assert_eq!(editor.width(), INITIAL_WIDTH + 1);
+        assert_eq!(editor.height(), INITIAL_HEIGHT + 1);
+        editor.tick(vec![ProtottyInput::Char('j'), ProtottyInput::Char('l')], &content);
+        assert_eq!(editor.width(), INITIAL_WIDTH + 1);
+        assert_eq!(editor.height(), INITIAL_HEIGHT + 1);
+    }
+}
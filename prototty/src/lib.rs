@@ -3,14 +3,20 @@ extern crate prototty;
 extern crate prototty_common;
 extern crate punchcards;
 extern crate rand;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
 
 use std::fmt::Write;
+use std::mem;
 use std::time::Duration;
 use rand::{Rng, SeedableRng, StdRng};
 use direction::CardinalDirection;
 use punchcards::state::*;
 use punchcards::tile::Tile;
 use punchcards::tile_info::TileInfo;
+use punchcards::intent::Intent;
 use prototty::*;
 use prototty::Input as ProtottyInput;
 use prototty::inputs as prototty_inputs;
@@ -18,16 +24,34 @@ use prototty_common::*;
 use punchcards::input::Input as PunchcardsInput;
 use punchcards::card::Card;
 use punchcards::card_state::CardState;
+use punchcards::script::ScriptLibrary;
+use punchcards::content::{Content, PackError, PackId, TileStyle};
 use punchcards::ExternalEvent;
 
 use self::CardinalDirection::*;
 
-const SAVE_PERIOD_MS: u64 = 10000;
+mod editor;
+mod save_file;
+mod recovery;
+pub mod cast;
+pub mod replay;
+pub mod cli;
+pub mod session;
+pub mod locations;
+pub mod settings;
+
+use editor::{Editor, EditorAction};
+use settings::{Glyphs, Setting, Settings};
+use recovery::{Recovery, RecoveryChoice, RecoveryView};
+
 const SAVE_FILE: &'static str = "save";
+const SETTINGS_FILE: &'static str = "settings";
+const EXPORT_FILE: &'static str = "corrupt_save";
+const PACK_DIR: &'static str = "packs";
+const EDITOR_LEVEL_FILE: &'static str = "editor_level";
 
-const GAME_OVER_MS: u64 = 1000;
-const GAME_HEIGHT: u32 = 10;
-const GAME_WIDTH: u32 = 10;
+const VICTORY_MS: u64 = 3000;
+const BANNER_MS: u64 = 2000;
 const HAND_WIDTH: u32 = 12;
 const HAND_HEIGHT: u32 = 8;
 const DECK_WIDTH: u32 = 8;
@@ -35,8 +59,12 @@ const DECK_HEIGHT: u32 = 1;
 const GAME_PADDING_BOTTOM: u32 = 1;
 const GAME_PADDING_RIGHT: u32 = 1;
 
+const INTENT_DEPTH: i32 = 16;
+
 const TITLE_WIDTH: u32 = 16;
-const TITLE_HEIGHT: u32 = 6;
+const TITLE_HEIGHT: u32 = 8;
+const OPTIONS_WIDTH: u32 = 20;
+const OPTIONS_HEIGHT: u32 = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Frontend {
@@ -54,35 +82,49 @@ impl Frontend {
     }
 }
 
-fn view_tile<C: ViewCell>(tile_info: TileInfo, cell: &mut C) {
+fn view_tile_style<C: ViewCell>(style: TileStyle, cell: &mut C) {
+    let colour = style.colour;
+    cell.set_bold(true);
+    cell.set_character(style.glyph);
+    cell.set_foreground_colour(Rgb24::new(colour[0], colour[1], colour[2]));
+}
+
+fn view_tile<C: ViewCell>(tile_info: TileInfo, content: &Content, glyphs: Glyphs, cell: &mut C) {
+    if let Some(style) = content.tile_style(tile_info.tile) {
+        view_tile_style(style, cell);
+        return;
+    }
+    // unicode glyphs, where chosen, replace the ascii ones below
+    cell.set_character(tile_info.tile.ascii_glyph());
     match tile_info.tile {
         Tile::Player => {
             cell.set_bold(true);
-            cell.set_character('@');
             cell.set_foreground_colour(colours::WHITE);
         }
         Tile::Wall => {
             cell.set_foreground_colour(colours::BLACK);
             cell.set_background_colour(colours::WHITE);
-            cell.set_character('#');
         }
         Tile::Floor => {
             cell.set_foreground_colour(Rgb24::new(127, 127, 127));
-            cell.set_character('.');
         }
         Tile::CardMove => {
             cell.set_foreground_colour(colours::YELLOW);
             cell.set_bold(true);
-            cell.set_character('m');
+        }
+        Tile::CardSpecial => {
+            cell.set_foreground_colour(colours::BRIGHT_MAGENTA);
+            cell.set_bold(true);
         }
         Tile::Punch(direction) => {
-            let ch = match direction {
-                North => '↑',
-                South => '↓',
-                East => '→',
-                West => '←',
-            };
-            cell.set_character(ch);
+            if glyphs == Glyphs::Unicode {
+                cell.set_character(match direction {
+                    North => '↑',
+                    South => '↓',
+                    East => '→',
+                    West => '←',
+                });
+            }
             cell.set_foreground_colour(colours::CYAN);
             cell.set_bold(false);
         }
@@ -93,26 +135,68 @@ fn view_tile<C: ViewCell>(tile_info: TileInfo, cell: &mut C) {
                 cell.set_foreground_colour(colours::BRIGHT_BLUE);
             }
             cell.set_bold(true);
-            cell.set_character('0');
         }
         Tile::SmallRobot => {
             cell.set_foreground_colour(colours::BRIGHT_GREEN);
             cell.set_bold(true);
-            cell.set_character('1');
+        }
+        Tile::EliteRobot => {
+            cell.set_foreground_colour(colours::BRIGHT_RED);
+            cell.set_bold(true);
+        }
+        Tile::GunRobot => {
+            cell.set_foreground_colour(colours::BRIGHT_YELLOW);
+            cell.set_bold(true);
+        }
+        Tile::Guard => {
+            cell.set_foreground_colour(colours::BRIGHT_MAGENTA);
+            cell.set_bold(true);
+        }
+        Tile::Skirmisher => {
+            cell.set_foreground_colour(colours::BRIGHT_CYAN);
+            cell.set_bold(true);
+        }
+        Tile::Champion(phase) => {
+            let background = match phase {
+                0 => Rgb24::new(63, 0, 0),
+                1 => Rgb24::new(127, 0, 0),
+                _ => Rgb24::new(191, 0, 0),
+            };
+            cell.set_foreground_colour(colours::WHITE);
+            cell.set_background_colour(background);
+            cell.set_bold(true);
         }
         Tile::Stairs => {
             cell.set_foreground_colour(colours::WHITE);
             cell.set_bold(true);
-            cell.set_character('>');
+        }
+        Tile::UpStairs => {
+            cell.set_foreground_colour(colours::WHITE);
+            cell.set_bold(true);
         }
         Tile::Bullet => {
             cell.set_foreground_colour(colours::WHITE);
             cell.set_bold(true);
-            cell.set_character('•');
+            if glyphs == Glyphs::Unicode {
+                cell.set_character('•');
+            }
+        }
+        Tile::PackEnemy(_) => {
+            cell.set_foreground_colour(colours::WHITE);
         }
     }
 }
 
+fn view_intent<C: ViewCell>(intent: Intent, cell: &mut C) {
+    match intent {
+        Intent::Move(_) => cell.set_background_colour(Rgb24::new(31, 31, 31)),
+        Intent::Attack(_) => cell.set_background_colour(Rgb24::new(127, 0, 0)),
+        Intent::Shoot(_) => cell.set_background_colour(Rgb24::new(63, 0, 0)),
+        Intent::Charge(_) => cell.set_background_colour(Rgb24::new(95, 0, 63)),
+        Intent::Summon => cell.set_background_colour(Rgb24::new(0, 31, 63)),
+    }
+}
+
 const INITIAL_INPUT_BUFFER_SIZE: usize = 16;
 
 struct DeckView {
@@ -151,17 +235,18 @@ impl ViewSize<State> for HandView {
     }
 }
 
-fn write_card(card: Card, string: &mut String) {
+fn write_card(card: Card, scripts: &ScriptLibrary, string: &mut String) {
     match card {
         Card::Move => write!(string, "Move").unwrap(),
         Card::Punch => write!(string, "Punch").unwrap(),
         Card::Shoot => write!(string, "Shoot").unwrap(),
+        Card::Scripted(script_id) => write!(string, "{}", scripts.name(script_id)).unwrap(),
     }
 }
 
-fn maybe_write_card(card: Option<Card>, string: &mut String) {
+fn maybe_write_card(card: Option<Card>, scripts: &ScriptLibrary, string: &mut String) {
     if let Some(card) = card {
-        write_card(card, string);
+        write_card(card, scripts, string);
     } else {
         write!(string, "-").unwrap();
     }
@@ -198,7 +283,7 @@ impl View<State> for HandView {
 
         for (i, maybe_card) in state.card_state().hand.iter().enumerate() {
             write!(&mut self.scratch, "{}: ", i + 1).unwrap();
-            maybe_write_card(*maybe_card, &mut self.scratch);
+            maybe_write_card(*maybe_card, state.scripts(), &mut self.scratch);
 
             if Some(i) == selected_index {
                 self.selected_view.view(
@@ -220,13 +305,35 @@ impl View<State> for HandView {
 enum AppState {
     Game,
     GameOver,
+    Victory,
+    TutorialComplete,
     MainMenu,
+    Options,
+    Recovery,
+    Editor,
+    Spectating,
+}
+
+// what a spectator needs to draw the screen of another running app
+#[derive(Serialize, Deserialize)]
+pub enum SpectatorFrame {
+    Waiting,
+    Game(ViewState),
+    GameOver,
+    Victory,
+    TutorialComplete,
 }
 
 pub enum ControlFlow {
     Quit,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detour {
+    Playtest,
+    Tutorial,
+}
+
 enum InputType {
     Game(PunchcardsInput),
     ControlFlow(ControlFlow),
@@ -235,13 +342,30 @@ enum InputType {
 #[derive(Debug, Clone, Copy)]
 enum MainMenuChoice {
     NewGame,
+    Tutorial,
+    Editor,
     Continue,
     SaveAndQuit,
     Save,
     Quit,
     ClearData,
+    Options,
+    Recover,
 }
 
+#[derive(Debug, Clone, Copy)]
+enum OptionsChoice {
+    Change(Setting),
+    Back,
+}
+
+const SETTINGS: &'static [Setting] = &[
+    Setting::AnimationSpeed,
+    Setting::Glyphs,
+    Setting::Autosave,
+    Setting::GameOverDelay,
+];
+
 struct TitleScreenView {
     title_view: RichStringView,
     main_menu_view: DefaultMenuInstanceView,
@@ -256,10 +380,29 @@ impl TitleScreenView {
     }
 }
 
+struct OptionsView {
+    title_view: RichStringView,
+    options_menu_view: DefaultMenuInstanceView,
+}
+
+impl OptionsView {
+    fn new() -> Self {
+        Self {
+            title_view: RichStringView::with_info(TextInfo::default().bold().underline()),
+            options_menu_view: DefaultMenuInstanceView,
+        }
+    }
+}
+
 pub struct AppView {
+    scratch: String,
+    banner_view: RichStringView,
+    prompt_view: RichStringView,
     deck_view: Decorated<DeckView, Border>,
     hand_view: Decorated<HandView, Border>,
     title_screen_view: Decorated<TitleScreenView, Align>,
+    options_view: Decorated<OptionsView, Align>,
+    recovery_view: Decorated<RecoveryView, Align>,
 }
 
 impl View<MenuInstance<MainMenuChoice>> for TitleScreenView {
@@ -281,17 +424,54 @@ impl ViewSize<MenuInstance<MainMenuChoice>> for TitleScreenView {
     }
 }
 
+impl View<MenuInstance<OptionsChoice>> for OptionsView {
+    fn view<G: ViewGrid>(
+        &mut self,
+        menu: &MenuInstance<OptionsChoice>,
+        offset: Coord,
+        depth: i32,
+        grid: &mut G,
+    ) {
+        self.title_view.view("Options", offset, depth, grid);
+        self.options_menu_view
+            .view(menu, offset + Coord::new(0, 2), depth, grid);
+    }
+}
+impl ViewSize<MenuInstance<OptionsChoice>> for OptionsView {
+    fn size(&mut self, _menu: &MenuInstance<OptionsChoice>) -> Size {
+        Size::new(OPTIONS_WIDTH, OPTIONS_HEIGHT)
+    }
+}
+
 impl AppView {
     pub fn new(size: Size) -> Self {
         let align = Align::new(size, Alignment::Centre, Alignment::Centre);
+        let mut banner_view = RichStringView::new();
+        banner_view.info.foreground_colour = Some(colours::BRIGHT_RED);
+        banner_view.info.bold = true;
+        let mut prompt_view = RichStringView::new();
+        prompt_view.info.foreground_colour = Some(colours::BRIGHT_YELLOW);
         Self {
+            scratch: String::new(),
+            banner_view,
+            prompt_view,
             deck_view: Decorated::new(DeckView::new(), Border::with_title("Deck")),
             hand_view: Decorated::new(HandView::new(), Border::with_title("Hand")),
             title_screen_view: Decorated::new(TitleScreenView::new(), align),
+            options_view: Decorated::new(
+                OptionsView::new(),
+                Align::new(size, Alignment::Centre, Alignment::Centre),
+            ),
+            recovery_view: Decorated::new(
+                RecoveryView::new(),
+                Align::new(size, Alignment::Centre, Alignment::Centre),
+            ),
         }
     }
     pub fn set_size(&mut self, size: Size) {
         self.title_screen_view.decorator.size = size;
+        self.options_view.decorator.size = size;
+        self.recovery_view.decorator.size = size;
     }
 }
 
@@ -304,8 +484,22 @@ pub struct App<S: Storage> {
     game_over_duration: Duration,
     rng: StdRng,
     storage: S,
+    // settings are kept apart from saves when the frontend has somewhere for them
+    settings_storage: Option<S>,
+    settings: Settings,
+    recovery: Option<Recovery>,
+    options_menu: MenuInstance<OptionsChoice>,
     frontend: Frontend,
     save_remaining: Duration,
+    saved_turn: Option<u64>,
+    banner: Option<&'static str>,
+    banner_duration: Duration,
+    content: Content,
+    status: Option<String>,
+    keep_save: bool,
+    editor: Editor,
+    // the real game, while a playtest or the tutorial is played in its place
+    suspended: Option<(Detour, State)>,
 }
 
 impl<S: Storage> View<App<S>> for AppView {
@@ -314,6 +508,9 @@ impl<S: Storage> View<App<S>> for AppView {
             AppState::MainMenu => {
                 self.title_screen_view
                     .view(&app.main_menu, offset, depth, grid);
+                if let Some(ref status) = app.status {
+                    self.banner_view.view(status, offset, depth, grid);
+                }
             }
             AppState::Game => {
                 let entity_store = app.state.entity_store();
@@ -324,21 +521,82 @@ impl<S: Storage> View<App<S>> for AppView {
                             offset + Coord::new(coord.x, coord.y),
                             tile_info.depth + depth,
                         ) {
-                            view_tile(*tile_info, cell);
+                            view_tile(*tile_info, app.state.content(), app.settings.glyphs, cell);
                         }
                     }
                 }
 
+                app.state.for_each_intent_cell(|coord, intent| {
+                    if let Some(cell) =
+                        grid.get_mut(offset + Coord::new(coord.x, coord.y), INTENT_DEPTH + depth)
+                    {
+                        view_intent(intent, cell);
+                    }
+                });
+
+                let game_size = app.state.size();
+
                 self.deck_view.view(
                     app.state.card_state(),
-                    offset + Coord::new(0, GAME_HEIGHT as i32 + GAME_PADDING_BOTTOM as i32),
+                    offset + Coord::new(0, (game_size.height() + GAME_PADDING_BOTTOM) as i32),
                     depth,
                     grid,
                 );
 
                 self.hand_view.view(
                     &app.state,
-                    offset + Coord::new(GAME_WIDTH as i32 + GAME_PADDING_RIGHT as i32, 0),
+                    offset + Coord::new((game_size.width() + GAME_PADDING_RIGHT) as i32, 0),
+                    depth,
+                    grid,
+                );
+
+                write!(
+                    &mut self.scratch,
+                    "Floor {}/{}",
+                    app.state.depth() + 1,
+                    app.state.num_floors()
+                ).unwrap();
+                StringView.view(
+                    &self.scratch,
+                    offset + Coord::new(
+                        (game_size.width() + GAME_PADDING_RIGHT) as i32,
+                        HAND_HEIGHT as i32 + 2,
+                    ),
+                    depth,
+                    grid,
+                );
+                self.scratch.clear();
+
+                if let Some(banner) = app.banner {
+                    self.banner_view.view(
+                        &banner,
+                        offset + Coord::new(
+                            0,
+                            (game_size.height() + GAME_PADDING_BOTTOM + DECK_HEIGHT + 2) as i32,
+                        ),
+                        depth,
+                        grid,
+                    );
+                }
+
+                if let Some(prompt) = app.state.tutorial_prompt() {
+                    let top = game_size.height() + GAME_PADDING_BOTTOM + DECK_HEIGHT + 3;
+                    for (i, line) in prompt.iter().enumerate() {
+                        self.prompt_view.view(
+                            line,
+                            offset + Coord::new(0, top as i32 + i as i32),
+                            depth,
+                            grid,
+                        );
+                    }
+                }
+            }
+            AppState::Editor => {
+                app.editor.view(
+                    &app.content,
+                    app.settings.glyphs,
+                    &mut self.scratch,
+                    offset,
                     depth,
                     grid,
                 );
@@ -346,11 +604,53 @@ impl<S: Storage> View<App<S>> for AppView {
             AppState::GameOver => {
                 StringView.view(&"Game Over", offset, depth, grid);
             }
+            AppState::Victory => {
+                StringView.view(&"You are the champion!", offset, depth, grid);
+            }
+            AppState::TutorialComplete => {
+                StringView.view(&"Tutorial complete!", offset, depth, grid);
+            }
+            AppState::Spectating => {
+                StringView.view(&"Waiting for a game to watch...", offset, depth, grid);
+                if let Some(ref status) = app.status {
+                    self.banner_view
+                        .view(status, offset + Coord::new(0, 2), depth, grid);
+                }
+            }
+            AppState::Options => {
+                self.options_view
+                    .view(&app.options_menu, offset, depth, grid);
+            }
+            AppState::Recovery => {
+                if let Some(ref recovery) = app.recovery {
+                    self.recovery_view.view(recovery, offset, depth, grid);
+                }
+            }
         }
     }
 }
 
-fn make_main_menu(in_progress: bool, frontend: Frontend) -> MenuInstance<MainMenuChoice> {
+fn boss_phase_banner(phase: u32) -> &'static str {
+    match phase {
+        1 => "The champion charges!",
+        _ => "The champion calls for help!",
+    }
+}
+
+fn load_content<S: Storage>(storage: &S) -> Result<Content, PackError> {
+    Content::load(|path| {
+        storage
+            .load_raw(format!("{}/{}", PACK_DIR, path))
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+    })
+}
+
+fn make_main_menu(
+    in_progress: bool,
+    damaged_save: bool,
+    frontend: Frontend,
+) -> MenuInstance<MainMenuChoice> {
     let menu_items = if in_progress {
         vec![
             Some(("Continue", MainMenuChoice::Continue)),
@@ -360,39 +660,116 @@ fn make_main_menu(in_progress: bool, frontend: Frontend) -> MenuInstance<MainMen
                 Some(("Save", MainMenuChoice::Save))
             },
             Some(("New Game", MainMenuChoice::NewGame)),
+            Some(("Tutorial", MainMenuChoice::Tutorial)),
+            Some(("Level Editor", MainMenuChoice::Editor)),
+            Some(("Options", MainMenuChoice::Options)),
             Some(("Clear Data", MainMenuChoice::ClearData)),
         ].into_iter()
             .filter_map(|x| x)
             .collect()
     } else {
         vec![
-            ("New Game", MainMenuChoice::NewGame),
-            ("Quit", MainMenuChoice::Quit),
-        ]
+            if damaged_save {
+                Some(("Damaged Save", MainMenuChoice::Recover))
+            } else {
+                None
+            },
+            Some(("New Game", MainMenuChoice::NewGame)),
+            Some(("Tutorial", MainMenuChoice::Tutorial)),
+            Some(("Level Editor", MainMenuChoice::Editor)),
+            Some(("Options", MainMenuChoice::Options)),
+            Some(("Quit", MainMenuChoice::Quit)),
+        ].into_iter()
+            .filter_map(|x| x)
+            .collect()
     };
     let main_menu = Menu::smallest(menu_items);
     MenuInstance::new(main_menu).unwrap()
 }
 
+fn missing_pack(state: &SaveState, content: &Content) -> Option<String> {
+    state
+        .packs()
+        .iter()
+        .find(|pack| !content.packs().contains(pack))
+        .map(|pack| pack.name.clone())
+}
+
+fn make_options_menu(settings: &Settings, index: usize) -> MenuInstance<OptionsChoice> {
+    let mut menu_items = SETTINGS
+        .iter()
+        .map(|&setting| (settings.label(setting), OptionsChoice::Change(setting)))
+        .collect::<Vec<_>>();
+    menu_items.push(("Back".to_string(), OptionsChoice::Back));
+    MenuInstance::with_index(Menu::smallest(menu_items), index).unwrap()
+}
+
 impl<S: Storage> App<S> {
     pub fn new(frontend: Frontend, storage: S, seed: usize) -> Self {
+        Self::with_settings_storage(frontend, storage, None, seed)
+    }
+
+    pub fn with_settings_storage(
+        frontend: Frontend,
+        storage: S,
+        settings_storage: Option<S>,
+        seed: usize,
+    ) -> Self {
         let mut rng = StdRng::from_seed(&[seed]);
 
-        let existing_state: Option<SaveState> = storage.load(SAVE_FILE).ok();
+        let (content, mut status) = match load_content(&storage) {
+            Ok(content) => (content, None),
+            Err(error) => (Content::builtin(), Some(format!("Pack error: {}", error))),
+        };
 
-        let (in_progress, state) = if let Some(state) = existing_state {
-            (true, State::from(state))
-        } else {
-            (false, State::new(rng.gen()))
+        // a save that exists but won't load is kept until the player decides what to do with it
+        let loaded: Result<SaveState, _> = save_file::load(&storage, SAVE_FILE);
+        let (existing_state, recovery) = match loaded {
+            Ok(state) => (Some(state), None),
+            Err(LoadError::NoSuchKey) => (None, None),
+            Err(error) => {
+                let has_backup = storage
+                    .load_raw(save_file::backup_key(SAVE_FILE))
+                    .is_ok();
+                status = Some("Saved game couldn't be loaded".to_string());
+                (None, Some(Recovery::new(&error, has_backup)))
+            }
         };
 
-        let main_menu = make_main_menu(in_progress, frontend);
+        let settings: Settings = settings_storage
+            .as_ref()
+            .unwrap_or(&storage)
+            .load(SETTINGS_FILE)
+            .unwrap_or_default();
+
+        // a save made with packs that are no longer loaded is left on disk untouched
+        let mut keep_save = recovery.is_some();
+        let (in_progress, state) = match existing_state {
+            Some(state) => {
+                if let Some(name) = missing_pack(&state, &content) {
+                    status = Some(format!("Save needs pack: {}", name));
+                    keep_save = true;
+                    (false, State::with_content(rng.gen(), content.clone()))
+                } else {
+                    (true, State::from(state))
+                }
+            }
+            None => (false, State::with_content(rng.gen(), content.clone())),
+        };
+
+        let main_menu = make_main_menu(in_progress, recovery.is_some(), frontend);
 
         let app_state = AppState::MainMenu;
         let input_buffer = Vec::with_capacity(INITIAL_INPUT_BUFFER_SIZE);
         let game_over_duration = Duration::default();
 
-        let save_remaining = Duration::from_millis(SAVE_PERIOD_MS);
+        let save_remaining = settings.autosave_period();
+        let saved_turn = if in_progress {
+            Some(state.turn_count())
+        } else {
+            None
+        };
+        let options_menu = make_options_menu(&settings, 0);
 
         Self {
             main_menu,
@@ -402,36 +779,285 @@ impl<S: Storage> App<S> {
             input_buffer,
             game_over_duration,
             storage,
+            settings_storage,
+            settings,
+            recovery,
+            options_menu,
             rng,
             frontend,
             save_remaining,
+            saved_turn,
+            banner: None,
+            banner_duration: Duration::default(),
+            content,
+            status,
+            keep_save,
+            editor: Editor::new(),
+            suspended: None,
         }
     }
 
-    pub fn store(&mut self) {
+    fn start_detour(&mut self, detour: Detour, state: State) {
+        self.suspended = match self.suspended.take() {
+            Some((_, suspended)) => {
+                self.state = state;
+                Some((detour, suspended))
+            }
+            None => Some((detour, mem::replace(&mut self.state, state))),
+        };
+        self.app_state = AppState::Game;
+    }
+
+    fn end_detour(&mut self) {
+        if let Some((detour, state)) = self.suspended.take() {
+            self.state = state;
+            self.app_state = match detour {
+                Detour::Playtest => AppState::Editor,
+                Detour::Tutorial => AppState::MainMenu,
+            };
+        }
+    }
+
+    fn start_playtest(&mut self, rows: Vec<String>) {
+        let playtest = State::playtest(self.rng.gen(), rows, self.content.clone());
+        self.start_detour(Detour::Playtest, playtest);
+    }
+
+    fn editor_action(&mut self, action: EditorAction) {
+        match action {
+            EditorAction::Playtest(rows) => self.start_playtest(rows),
+            EditorAction::Save => {
+                let text = self.editor.to_text();
+                let status = match self.storage.store_raw(EDITOR_LEVEL_FILE, text.as_bytes()) {
+                    Ok(()) => "Saved",
+                    Err(_) => "Failed to save level",
+                };
+                self.editor.status = Some(status.to_string());
+            }
+            EditorAction::Load => {
+                let text = self.storage
+                    .load_raw(EDITOR_LEVEL_FILE)
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok());
+                match text {
+                    Some(text) => self.editor.load_text(&text),
+                    None => self.editor.status = Some("No saved level".to_string()),
+                }
+            }
+            EditorAction::Exit => self.app_state = AppState::MainMenu,
+        }
+    }
+
+    // returns false, and says why on the menu, if the save couldn't be written
+    pub fn store(&mut self) -> bool {
+        self.save_remaining = self.settings.autosave_period();
         if self.in_progress {
-            self.storage
-                .store(SAVE_FILE, &self.state.save(self.rng.gen()))
-                .expect("Failed to save");
+            // a playtest or tutorial is never saved in place of the game it interrupted
+            let (turn, save_state) = {
+                let state = self.suspended
+                    .as_ref()
+                    .map(|&(_, ref state)| state)
+                    .unwrap_or(&self.state);
+                (state.turn_count(), state.save(self.rng.gen()))
+            };
+            match save_file::store(&mut self.storage, SAVE_FILE, &save_state) {
+                Ok(()) => {
+                    self.saved_turn = Some(turn);
+                    true
+                }
+                Err(error) => {
+                    self.status = Some(format!("Save failed: {}", error));
+                    false
+                }
+            }
         } else {
-            match self.storage.remove_raw(SAVE_FILE) {
-                Err(LoadError::IoError) => eprintln!("Failed to delete game data"),
-                _ => (),
+            if !self.keep_save {
+                match save_file::remove(&mut self.storage, SAVE_FILE) {
+                    Err(LoadError::IoError) => {
+                        self.status = Some("Failed to delete game data".to_string())
+                    }
+                    _ => (),
+                }
+            }
+            true
+        }
+    }
+
+    // saves are only taken between turns, once the autosave period has passed
+    fn autosave(&mut self, period: Duration) {
+        if !self.in_progress || self.suspended.is_some() {
+            return;
+        }
+        self.save_remaining = self.save_remaining
+            .checked_sub(period)
+            .unwrap_or_default();
+        if self.save_remaining == Duration::default() && self.state.is_awaiting_input()
+            && self.saved_turn != Some(self.state.turn_count())
+        {
+            if !self.store() {
+                self.banner = Some("Autosave failed!");
+                self.banner_duration = Duration::from_millis(BANNER_MS);
+            }
+        }
+    }
+
+    fn recovery_status(&mut self, status: &str) {
+        if let Some(ref mut recovery) = self.recovery {
+            recovery.status = Some(status.to_string());
+        }
+    }
+
+    // leaves the recovery screen once the damaged save has been dealt with
+    fn end_recovery(&mut self, status: &str) {
+        self.recovery = None;
+        self.keep_save = false;
+        self.main_menu = make_main_menu(self.in_progress, false, self.frontend);
+        self.app_state = AppState::MainMenu;
+        self.status = Some(status.to_string());
+    }
+
+    fn export_damaged_save(&mut self) -> Result<(), &'static str> {
+        let bytes = self.storage
+            .load_raw(SAVE_FILE)
+            .map_err(|_| "Couldn't read the save")?;
+        self.storage
+            .store_raw(EXPORT_FILE, &bytes)
+            .map_err(|_| "Couldn't write the copy")
+    }
+
+    fn recover(&mut self, choice: RecoveryChoice) {
+        match choice {
+            RecoveryChoice::TryBackup => {
+                let backup: Result<SaveState, _> = save_file::load_backup(&self.storage, SAVE_FILE);
+                match backup {
+                    Ok(save_state) => {
+                        if let Some(name) = missing_pack(&save_state, &self.content) {
+                            self.recovery_status(&format!("Backup needs pack: {}", name));
+                        } else {
+                            self.state = State::from(save_state);
+                            self.in_progress = true;
+                            self.end_recovery("Restored the backup");
+                            self.store();
+                        }
+                    }
+                    Err(_) => self.recovery_status("The backup couldn't be loaded either"),
+                }
+            }
+            RecoveryChoice::Export => {
+                let status = match self.export_damaged_save() {
+                    Ok(()) => format!("Copied to \"{}\" by the save", EXPORT_FILE),
+                    Err(error) => error.to_string(),
+                };
+                self.recovery_status(&status);
+            }
+            RecoveryChoice::Discard => match save_file::discard(&mut self.storage, SAVE_FILE) {
+                Err(LoadError::IoError) => self.recovery_status("Couldn't delete the save"),
+                _ => self.end_recovery("Discarded the damaged save"),
+            },
+            RecoveryChoice::Back => self.app_state = AppState::MainMenu,
+        }
+    }
+
+    pub fn packs(&self) -> &[PackId] {
+        self.content.packs()
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    // applies settings without storing them, e.g. the settings a replay was recorded with
+    pub fn apply_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        self.save_remaining = settings.autosave_period();
+        let index = self.options_menu.index();
+        self.options_menu = make_options_menu(&self.settings, index);
+    }
+
+    fn change_setting(&mut self, setting: Setting) {
+        let mut settings = self.settings;
+        settings.cycle(setting);
+        self.apply_settings(settings);
+        let result = match self.settings_storage {
+            Some(ref mut settings_storage) => settings_storage.store(SETTINGS_FILE, &settings),
+            None => self.storage.store(SETTINGS_FILE, &settings),
+        };
+        if result.is_err() {
+            self.status = Some("Failed to save settings".to_string());
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        // the new game's save would replace a damaged one, so it's copied aside first
+        if self.recovery.is_some() {
+            if let Err(error) = self.export_damaged_save() {
+                self.recovery_status(error);
+                self.app_state = AppState::Recovery;
+                return;
             }
+            self.status = Some(format!("Damaged save copied to \"{}\"", EXPORT_FILE));
         }
+        self.state = State::with_content(self.rng.gen(), self.content.clone());
+        self.app_state = AppState::Game;
+        self.in_progress = true;
+        self.keep_save = false;
+        self.recovery = None;
+        self.main_menu = make_main_menu(true, false, self.frontend);
+        self.store();
+    }
+
+    pub fn spectator_frame(&self) -> SpectatorFrame {
+        match self.app_state {
+            AppState::Game => SpectatorFrame::Game(self.state.view_state()),
+            AppState::GameOver => SpectatorFrame::GameOver,
+            AppState::Victory => SpectatorFrame::Victory,
+            AppState::TutorialComplete => SpectatorFrame::TutorialComplete,
+            AppState::MainMenu
+            | AppState::Options
+            | AppState::Recovery
+            | AppState::Editor
+            | AppState::Spectating => SpectatorFrame::Waiting,
+        }
+    }
+
+    // shows a saved game, with the packs it was saved with, without it being played
+    pub fn show(&mut self, save_state: SaveState) {
+        self.state = State::from(save_state);
+        self.app_state = AppState::Game;
+    }
+
+    // a spectating app is never ticked, it just shows whatever frame it was given last
+    pub fn spectate(&mut self, frame: SpectatorFrame) {
+        self.app_state = match frame {
+            SpectatorFrame::Waiting => AppState::Spectating,
+            SpectatorFrame::Game(view_state) => {
+                let missing = view_state
+                    .packs()
+                    .iter()
+                    .find(|pack| !self.content.packs().contains(pack))
+                    .map(|pack| pack.name.clone());
+                match missing {
+                    Some(name) => {
+                        self.status = Some(format!("The game needs pack: {}", name));
+                        AppState::Spectating
+                    }
+                    None => {
+                        self.status = None;
+                        self.state = State::from_view_state(view_state, self.content.clone());
+                        AppState::Game
+                    }
+                }
+            }
+            SpectatorFrame::GameOver => AppState::GameOver,
+            SpectatorFrame::Victory => AppState::Victory,
+            SpectatorFrame::TutorialComplete => AppState::TutorialComplete,
+        };
     }
 
     pub fn tick<I>(&mut self, inputs: I, period: Duration) -> Option<ControlFlow>
     where
         I: IntoIterator<Item = ProtottyInput>,
     {
-        if period < self.save_remaining {
-            self.save_remaining -= period;
-        } else {
-            self.save_remaining = Duration::from_millis(SAVE_PERIOD_MS);
-            self.store();
-        }
-
         match self.app_state {
             AppState::MainMenu => {
                 if let Some(menu_output) = self.main_menu.tick(inputs) {
@@ -450,8 +1076,11 @@ impl<S: Storage> App<S> {
                                 None
                             }
                             MainMenuChoice::SaveAndQuit => {
-                                self.store();
-                                Some(ControlFlow::Quit)
+                                if self.store() {
+                                    Some(ControlFlow::Quit)
+                                } else {
+                                    None
+                                }
                             }
                             MainMenuChoice::Continue => {
                                 self.app_state = AppState::Game;
@@ -459,17 +1088,33 @@ impl<S: Storage> App<S> {
                                 None
                             }
                             MainMenuChoice::NewGame => {
-                                self.state = State::new(self.rng.gen());
-                                self.app_state = AppState::Game;
-                                self.in_progress = true;
-                                self.main_menu = make_main_menu(true, self.frontend);
-                                self.store();
+                                self.new_game();
+                                None
+                            }
+                            MainMenuChoice::Tutorial => {
+                                let tutorial = State::tutorial(self.rng.gen());
+                                self.start_detour(Detour::Tutorial, tutorial);
+                                None
+                            }
+                            MainMenuChoice::Editor => {
+                                self.app_state = AppState::Editor;
+                                None
+                            }
+                            MainMenuChoice::Options => {
+                                self.app_state = AppState::Options;
+                                None
+                            }
+                            MainMenuChoice::Recover => {
+                                self.app_state = AppState::Recovery;
                                 None
                             }
                             MainMenuChoice::ClearData => {
-                                self.state = State::new(self.rng.gen());
+                                self.state =
+                                    State::with_content(self.rng.gen(), self.content.clone());
                                 self.in_progress = false;
-                                self.main_menu = make_main_menu(false, self.frontend);
+                                self.keep_save = false;
+                                self.recovery = None;
+                                self.main_menu = make_main_menu(false, false, self.frontend);
                                 self.store();
                                 None
                             }
@@ -495,7 +1140,11 @@ impl<S: Storage> App<S> {
                         ProtottyInput::Char(' ') => InputType::Game(PunchcardsInput::Wait),
                         prototty_inputs::ETX => InputType::ControlFlow(ControlFlow::Quit),
                         prototty_inputs::ESCAPE => {
-                            self.app_state = AppState::MainMenu;
+                            if self.suspended.is_some() {
+                                self.end_detour();
+                            } else {
+                                self.app_state = AppState::MainMenu;
+                            }
                             break;
                         }
                         _ => continue,
@@ -508,25 +1157,89 @@ impl<S: Storage> App<S> {
                     }
                 }
 
-                if let Some(meta) = self.state.tick(self.input_buffer.drain(..), period) {
+                if let Some(remaining) = self.banner_duration.checked_sub(period) {
+                    self.banner_duration = remaining;
+                } else {
+                    self.banner = None;
+                }
+
+                let animation_period = self.settings.animation_speed.scale(period);
+                if let Some(meta) = self
+                    .state
+                    .tick(self.input_buffer.drain(..), animation_period)
+                {
                     match meta {
                         ExternalEvent::GameOver => {
                             self.app_state = AppState::GameOver;
-                            self.game_over_duration = Duration::from_millis(GAME_OVER_MS);
+                            self.game_over_duration = self.settings.game_over_delay();
+                        }
+                        ExternalEvent::Victory => {
+                            self.app_state = AppState::Victory;
+                            self.game_over_duration = Duration::from_millis(VICTORY_MS);
+                        }
+                        ExternalEvent::BossPhaseChanged(phase) => {
+                            self.banner = Some(boss_phase_banner(phase));
+                            self.banner_duration = Duration::from_millis(BANNER_MS);
+                        }
+                        ExternalEvent::TutorialComplete => {
+                            self.app_state = AppState::TutorialComplete;
+                            self.game_over_duration = Duration::from_millis(VICTORY_MS);
                         }
                     }
                 }
 
+                if let AppState::Game = self.app_state {
+                    self.autosave(period);
+                }
+
                 None
             }
-            AppState::GameOver => {
+            AppState::Options => {
+                if let Some(menu_output) = self.options_menu.tick(inputs) {
+                    match menu_output {
+                        MenuOutput::Quit => return Some(ControlFlow::Quit),
+                        MenuOutput::Cancel | MenuOutput::Finalise(OptionsChoice::Back) => {
+                            self.app_state = AppState::MainMenu;
+                        }
+                        MenuOutput::Finalise(OptionsChoice::Change(setting)) => {
+                            self.change_setting(setting);
+                        }
+                    }
+                }
+                None
+            }
+            AppState::Recovery => {
+                let menu_output = match self.recovery {
+                    Some(ref mut recovery) => recovery.menu.tick(inputs),
+                    None => Some(MenuOutput::Cancel),
+                };
+                match menu_output {
+                    Some(MenuOutput::Quit) => return Some(ControlFlow::Quit),
+                    Some(MenuOutput::Cancel) => self.app_state = AppState::MainMenu,
+                    Some(MenuOutput::Finalise(choice)) => self.recover(choice),
+                    None => (),
+                }
+                None
+            }
+            AppState::Editor => {
+                if let Some(action) = self.editor.tick(inputs, &self.content) {
+                    self.editor_action(action);
+                }
+                None
+            }
+            AppState::Spectating => None,
+            AppState::GameOver | AppState::Victory | AppState::TutorialComplete => {
                 if let Some(remaining) = self.game_over_duration.checked_sub(period) {
                     self.game_over_duration = remaining;
+                } else if self.suspended.is_some() {
+                    self.end_detour();
                 } else {
                     self.in_progress = false;
-                    self.main_menu = make_main_menu(false, self.frontend);
+                    // removes the last autosave, so a finished run can't be continued
+                    self.store();
+                    self.main_menu = make_main_menu(false, self.recovery.is_some(), self.frontend);
                     self.app_state = AppState::MainMenu;
-                    self.state = State::new(self.rng.gen());
+                    self.state = State::with_content(self.rng.gen(), self.content.clone());
                 }
                 None
             }
@@ -0,0 +1,103 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// where saves were kept before they moved to the platform's data directory
+pub const LEGACY_USER_DIR: &'static str = "user";
+const APP_DIR: &'static str = "punchcards";
+
+#[derive(Debug, Clone)]
+pub struct Locations {
+    // saves, packs and levels
+    pub data: PathBuf,
+    // settings
+    pub config: PathBuf,
+}
+
+fn legacy_dir() -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let exe_dir = exe.parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "executable has no directory"))?;
+    Ok(exe_dir.join(LEGACY_USER_DIR))
+}
+
+#[cfg(target_os = "linux")]
+fn base_dir(variable: &str, fallback: &str) -> io::Result<PathBuf> {
+    // relative paths in XDG variables are invalid and must be ignored
+    if let Some(dir) = env::var_os(variable).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Ok(dir);
+        }
+    }
+    env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(fallback))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))
+}
+
+#[cfg(target_os = "linux")]
+fn platform_locations() -> io::Result<Locations> {
+    Ok(Locations {
+        data: base_dir("XDG_DATA_HOME", ".local/share")?.join(APP_DIR),
+        config: base_dir("XDG_CONFIG_HOME", ".config")?.join(APP_DIR),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn platform_locations() -> io::Result<Locations> {
+    let dir = legacy_dir()?;
+    Ok(Locations {
+        data: dir.clone(),
+        config: dir,
+    })
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
+}
+
+impl Locations {
+    // an explicit user dir holds everything, as the legacy dir did
+    pub fn resolve(user_dir: Option<&str>) -> io::Result<Self> {
+        if let Some(dir) = user_dir {
+            return Ok(Locations {
+                data: PathBuf::from(dir),
+                config: PathBuf::from(dir),
+            });
+        }
+        let locations = platform_locations()?;
+        locations.migrate()?;
+        Ok(locations)
+    }
+
+    // copies a legacy user dir into a data dir that doesn't exist yet, so this happens once,
+    // and the legacy dir is left alone in case the install is read-only
+    fn migrate(&self) -> io::Result<()> {
+        if self.data.exists() {
+            return Ok(());
+        }
+        let legacy = match legacy_dir() {
+            Ok(legacy) => legacy,
+            Err(_) => return Ok(()),
+        };
+        if legacy == self.data || !legacy.is_dir() {
+            return Ok(());
+        }
+        if let Err(error) = copy_dir(&legacy, &self.data) {
+            // a partial copy would stop the migration being retried
+            let _ = fs::remove_dir_all(&self.data);
+            return Err(error);
+        }
+        Ok(())
+    }
+}
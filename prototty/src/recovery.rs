@@ -0,0 +1,94 @@
+use prototty::*;
+use prototty_common::*;
+
+const RECOVERY_WIDTH: u32 = 40;
+const RECOVERY_HEIGHT: u32 = 11;
+const MENU_TOP: i32 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RecoveryChoice {
+    TryBackup,
+    Export,
+    Discard,
+    Back,
+}
+
+// a save that exists but couldn't be loaded, waiting for the player to decide what to do
+pub struct Recovery {
+    pub problem: &'static str,
+    pub status: Option<String>,
+    pub menu: MenuInstance<RecoveryChoice>,
+}
+
+impl Recovery {
+    pub fn new(error: &LoadError, has_backup: bool) -> Self {
+        let problem = match error {
+            &LoadError::IoError => "It couldn't be read from disk.",
+            _ => "It's damaged or from another version.",
+        };
+        let menu_items = vec![
+            if has_backup {
+                Some(("Try Backup", RecoveryChoice::TryBackup))
+            } else {
+                None
+            },
+            Some(("Export Raw", RecoveryChoice::Export)),
+            Some(("Discard", RecoveryChoice::Discard)),
+            Some(("Back", RecoveryChoice::Back)),
+        ].into_iter()
+            .filter_map(|x| x)
+            .collect();
+        Self {
+            problem,
+            status: None,
+            menu: MenuInstance::new(Menu::smallest(menu_items)).unwrap(),
+        }
+    }
+}
+
+pub struct RecoveryView {
+    title_view: RichStringView,
+    status_view: RichStringView,
+    menu_view: DefaultMenuInstanceView,
+}
+
+impl RecoveryView {
+    pub fn new() -> Self {
+        let mut status_view = RichStringView::new();
+        status_view.info.foreground_colour = Some(colours::BRIGHT_YELLOW);
+        Self {
+            title_view: RichStringView::with_info(TextInfo::default().bold().underline()),
+            status_view,
+            menu_view: DefaultMenuInstanceView,
+        }
+    }
+}
+
+impl View<Recovery> for RecoveryView {
+    fn view<G: ViewGrid>(&mut self, recovery: &Recovery, offset: Coord, depth: i32, grid: &mut G) {
+        self.title_view.view("Damaged Save", offset, depth, grid);
+        StringView.view(
+            &"The saved game couldn't be loaded.",
+            offset + Coord::new(0, 2),
+            depth,
+            grid,
+        );
+        StringView.view(&recovery.problem, offset + Coord::new(0, 3), depth, grid);
+        self.menu_view
+            .view(&recovery.menu, offset + Coord::new(0, MENU_TOP), depth, grid);
+        if let Some(ref status) = recovery.status {
+            self.status_view.view(
+                status,
+                offset + Coord::new(0, RECOVERY_HEIGHT as i32 - 1),
+                depth,
+                grid,
+            );
+        }
+    }
+}
+
+impl ViewSize<Recovery> for RecoveryView {
+    fn size(&mut self, _recovery: &Recovery) -> Size {
+        Size::new(RECOVERY_WIDTH, RECOVERY_HEIGHT)
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+use prototty::Input as ProtottyInput;
+use punchcards::content::PackId;
+use settings::Settings;
+
+// mouse input isn't recorded, so replays of the level editor may differ
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ReplayInput {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl ReplayInput {
+    pub fn from_input(input: ProtottyInput) -> Option<Self> {
+        match input {
+            ProtottyInput::Char(ch) => Some(ReplayInput::Char(ch)),
+            ProtottyInput::Up => Some(ReplayInput::Up),
+            ProtottyInput::Down => Some(ReplayInput::Down),
+            ProtottyInput::Left => Some(ReplayInput::Left),
+            ProtottyInput::Right => Some(ReplayInput::Right),
+            _ => None,
+        }
+    }
+
+    pub fn input(self) -> ProtottyInput {
+        match self {
+            ReplayInput::Char(ch) => ProtottyInput::Char(ch),
+            ReplayInput::Up => ProtottyInput::Up,
+            ReplayInput::Down => ProtottyInput::Down,
+            ReplayInput::Left => ProtottyInput::Left,
+            ReplayInput::Right => ProtottyInput::Right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayTick {
+    pub millis: u64,
+    pub inputs: Vec<ReplayInput>,
+}
+
+impl ReplayTick {
+    pub fn period(&self) -> Duration {
+        Duration::from_millis(self.millis)
+    }
+
+    pub fn inputs(&self) -> Vec<ProtottyInput> {
+        self.inputs.iter().map(|input| input.input()).collect()
+    }
+}
+
+// every tick is kept, since the length of a tick decides when buffered inputs are read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: usize,
+    pub width: u32,
+    pub height: u32,
+    // whether the menu was skipped straight into a new game, which recording now requires
+    #[serde(default)]
+    pub new_game: bool,
+    // animation speed decides when inputs are read, so it must match on playback
+    #[serde(default)]
+    pub settings: Settings,
+    // the packs loaded while recording, which playback can't load into its fresh user dir
+    #[serde(default)]
+    pub packs: Vec<PackId>,
+    pub ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn new(
+        seed: usize,
+        width: u32,
+        height: u32,
+        new_game: bool,
+        settings: Settings,
+        packs: Vec<PackId>,
+    ) -> Self {
+        Self {
+            seed,
+            width,
+            height,
+            new_game,
+            settings,
+            packs,
+            ticks: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, period: Duration, inputs: &[ProtottyInput]) {
+        let millis = period.as_secs() * 1000 + (period.subsec_nanos() / 1_000_000) as u64;
+        self.ticks.push(ReplayTick {
+            millis,
+            inputs: inputs
+                .iter()
+                .cloned()
+                .filter_map(ReplayInput::from_input)
+                .collect(),
+        });
+    }
+}
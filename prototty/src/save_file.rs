@@ -0,0 +1,212 @@
+use std::fmt;
+use prototty::{LoadError, Storage};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub fn temp_key(key: &str) -> String {
+    format!("{}.tmp", key)
+}
+
+pub fn backup_key(key: &str) -> String {
+    format!("{}.bak", key)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveError {
+    Write,
+    Verify,
+    Backup,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &SaveError::Write => write!(f, "couldn't write save"),
+            &SaveError::Verify => write!(f, "save didn't read back"),
+            &SaveError::Backup => write!(f, "couldn't back up save"),
+        }
+    }
+}
+
+// Storage can't rename, so the value is written to a temp key and read back before it's
+// copied over the real key. Until that copy finishes the temp key stays behind, and `load`
+// falls back to it. The save being replaced becomes the backup, unless it doesn't load.
+pub fn store<S, T>(storage: &mut S, key: &str, value: &T) -> Result<(), SaveError>
+where
+    S: Storage,
+    T: Serialize + DeserializeOwned,
+{
+    let temp = temp_key(key);
+    storage.store(&temp, value).map_err(|_| SaveError::Write)?;
+    let written: Result<T, _> = storage.load(&temp);
+    let bytes = match (written, storage.load_raw(&temp)) {
+        (Ok(_), Ok(bytes)) => bytes,
+        _ => return Err(SaveError::Verify),
+    };
+
+    let replaced: Result<T, _> = storage.load(key);
+    if replaced.is_ok() {
+        let previous = storage.load_raw(key).map_err(|_| SaveError::Backup)?;
+        storage
+            .store_raw(backup_key(key), &previous)
+            .map_err(|_| SaveError::Backup)?;
+    }
+
+    storage.store_raw(key, &bytes).map_err(|_| SaveError::Write)?;
+    let _ = storage.remove_raw(&temp);
+    Ok(())
+}
+
+// an error other than NoSuchKey means there's a save that can't be used
+pub fn load<S, T>(storage: &S, key: &str) -> Result<T, LoadError>
+where
+    S: Storage,
+    T: DeserializeOwned,
+{
+    storage
+        .load(key)
+        .or_else(|error| storage.load(temp_key(key)).map_err(|_| error))
+}
+
+pub fn load_backup<S, T>(storage: &S, key: &str) -> Result<T, LoadError>
+where
+    S: Storage,
+    T: DeserializeOwned,
+{
+    storage.load(backup_key(key))
+}
+
+// the backup is kept, since it may be all that's left of the game
+pub fn discard<S: Storage>(storage: &mut S, key: &str) -> Result<(), LoadError> {
+    let _ = storage.remove_raw(temp_key(key));
+    storage.remove_raw(key).map(|_| ())
+}
+
+pub fn remove<S: Storage>(storage: &mut S, key: &str) -> Result<(), LoadError> {
+    let _ = storage.remove_raw(backup_key(key));
+    discard(storage, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use prototty::StoreError;
+    use super::*;
+
+    const KEY: &'static str = "save";
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        values: HashMap<String, Vec<u8>>,
+    }
+
+    impl Storage for MemoryStorage {
+        fn exists<K: AsRef<str>>(&self, key: K) -> bool {
+            self.values.contains_key(key.as_ref())
+        }
+
+        fn clear(&mut self) {
+            self.values.clear();
+        }
+
+        fn remove_raw<K: AsRef<str>>(&mut self, key: K) -> Result<Vec<u8>, LoadError> {
+            self.values.remove(key.as_ref()).ok_or(LoadError::NoSuchKey)
+        }
+
+        fn load_raw<K: AsRef<str>>(&self, key: K) -> Result<Vec<u8>, LoadError> {
+            self.values
+                .get(key.as_ref())
+                .cloned()
+                .ok_or(LoadError::NoSuchKey)
+        }
+
+        fn store_raw<K: AsRef<str>, V: AsRef<[u8]>>(
+            &mut self,
+            key: K,
+            value: V,
+        ) -> Result<(), StoreError> {
+            self.values
+                .insert(key.as_ref().to_string(), value.as_ref().to_vec());
+            Ok(())
+        }
+    }
+
+    // too short to be read back as a u64
+    fn damage(storage: &mut MemoryStorage, key: &str) {
+        storage.store_raw(key, b"x").unwrap();
+    }
+
+    fn stored(value: u64) -> MemoryStorage {
+        let mut storage = MemoryStorage::default();
+        store(&mut storage, KEY, &value).unwrap();
+        storage
+    }
+
+    #[test]
+    fn a_stored_value_loads_and_leaves_no_temp_key() {
+        let storage = stored(1);
+        assert_eq!(load::<_, u64>(&storage, KEY), Ok(1));
+        assert!(!storage.exists(temp_key(KEY)));
+        assert!(!storage.exists(backup_key(KEY)));
+    }
+
+    #[test]
+    fn storing_again_backs_up_the_previous_save() {
+        let mut storage = stored(1);
+        store(&mut storage, KEY, &2u64).unwrap();
+        assert_eq!(load::<_, u64>(&storage, KEY), Ok(2));
+        assert_eq!(load_backup::<_, u64>(&storage, KEY), Ok(1));
+    }
+
+    #[test]
+    fn a_damaged_save_falls_back_to_the_temp_key() {
+        let mut storage = stored(1);
+        let bytes = storage.load_raw(KEY).unwrap();
+        storage.store_raw(temp_key(KEY), &bytes).unwrap();
+        damage(&mut storage, KEY);
+        assert_eq!(load::<_, u64>(&storage, KEY), Ok(1));
+    }
+
+    #[test]
+    fn a_damaged_save_without_a_temp_key_is_an_error() {
+        let mut storage = stored(1);
+        damage(&mut storage, KEY);
+        let loaded = load::<_, u64>(&storage, KEY);
+        assert!(loaded.is_err());
+        assert_ne!(loaded, Err(LoadError::NoSuchKey));
+    }
+
+    #[test]
+    fn a_missing_save_is_no_such_key() {
+        let storage = MemoryStorage::default();
+        assert_eq!(load::<_, u64>(&storage, KEY), Err(LoadError::NoSuchKey));
+    }
+
+    #[test]
+    fn a_save_that_wont_load_doesnt_replace_the_backup() {
+        let mut storage = stored(1);
+        store(&mut storage, KEY, &2u64).unwrap();
+        damage(&mut storage, KEY);
+        store(&mut storage, KEY, &3u64).unwrap();
+        assert_eq!(load::<_, u64>(&storage, KEY), Ok(3));
+        assert_eq!(load_backup::<_, u64>(&storage, KEY), Ok(1));
+    }
+
+    #[test]
+    fn discard_keeps_the_backup() {
+        let mut storage = stored(1);
+        store(&mut storage, KEY, &2u64).unwrap();
+        storage.store_raw(temp_key(KEY), b"x").unwrap();
+        discard(&mut storage, KEY).unwrap();
+        assert_eq!(load::<_, u64>(&storage, KEY), Err(LoadError::NoSuchKey));
+        assert_eq!(load_backup::<_, u64>(&storage, KEY), Ok(1));
+    }
+
+    #[test]
+    fn remove_deletes_the_backup_too() {
+        let mut storage = stored(1);
+        store(&mut storage, KEY, &2u64).unwrap();
+        remove(&mut storage, KEY).unwrap();
+        assert!(storage.values.is_empty());
+    }
+}
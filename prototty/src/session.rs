@@ -0,0 +1,127 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use std::vec;
+use serde_json;
+use prototty::{Size, Storage};
+use prototty::Input as ProtottyInput;
+use prototty::inputs as prototty_inputs;
+use replay::{Replay, ReplayTick};
+use cli::CommandLine;
+use settings::Settings;
+use App;
+
+fn json_error(error: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+// replays are played without packs, so one recorded with packs would play out differently
+pub fn load_replay(path: &str) -> io::Result<Replay> {
+    let file = File::open(path)?;
+    let replay: Replay = serde_json::from_reader(BufReader::new(file)).map_err(json_error)?;
+    if let Some(pack) = replay.packs.first() {
+        let message = format!("Replay needs pack: {}", pack.name);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    Ok(replay)
+}
+
+// the replay and recording options of a native frontend, shared by its main loop
+pub struct Session {
+    seed: usize,
+    new_game: bool,
+    playback: Option<vec::IntoIter<ReplayTick>>,
+    playback_settings: Option<Settings>,
+    record_path: Option<String>,
+    recording: Option<(String, Replay)>,
+    scratch_dir: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn new(command_line: &CommandLine, random_seed: usize) -> io::Result<Self> {
+        let playback = match command_line.play_replay {
+            Some(ref path) => Some(load_replay(path)?),
+            None => None,
+        };
+        // a replay only plays back correctly from the seed it was recorded with
+        let (seed, new_game) = match playback {
+            Some(ref replay) => (replay.seed, replay.new_game),
+            None => (
+                command_line.seed.unwrap_or(random_seed),
+                command_line.new_game,
+            ),
+        };
+        let scratch_dir = playback.as_ref().map(|_| {
+            env::temp_dir().join(format!("punchcards_replay_{}", process::id()))
+        });
+        Ok(Self {
+            seed,
+            new_game,
+            playback_settings: playback.as_ref().map(|replay| replay.settings),
+            playback: playback.map(|replay| replay.ticks.into_iter()),
+            record_path: command_line.record_replay.clone(),
+            recording: None,
+            scratch_dir,
+        })
+    }
+
+    pub fn seed(&self) -> usize {
+        self.seed
+    }
+
+    // a replay is played against an empty directory, so it can't touch the player's saves
+    // or settings, the way replay_cast and screenshot play them
+    pub fn scratch_dir(&self) -> Option<&PathBuf> {
+        self.scratch_dir.as_ref()
+    }
+
+    pub fn start<S: Storage>(&mut self, app: &mut App<S>, size: Size) {
+        if let Some(settings) = self.playback_settings {
+            app.apply_settings(settings);
+        }
+        if self.new_game {
+            app.new_game();
+        }
+        let (seed, new_game, settings) = (self.seed, self.new_game, app.settings());
+        let packs = app.packs().to_vec();
+        self.recording = self.record_path.take().map(|path| {
+            let replay = Replay::new(seed, size.width(), size.height(), new_game, settings, packs);
+            (path, replay)
+        });
+    }
+
+    // the player takes over once the replay runs out, and can stop it early with ctrl-c
+    pub fn tick(
+        &mut self,
+        live_inputs: Vec<ProtottyInput>,
+        live_period: Duration,
+    ) -> Option<(Vec<ProtottyInput>, Duration)> {
+        let (inputs, period) = match self.playback.as_mut().and_then(Iterator::next) {
+            Some(tick) => {
+                if live_inputs.contains(&prototty_inputs::ETX) {
+                    return None;
+                }
+                (tick.inputs(), tick.period())
+            }
+            None => (live_inputs, live_period),
+        };
+        if let Some((_, ref mut replay)) = self.recording {
+            replay.record(period, &inputs);
+        }
+        Some((inputs, period))
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        if let Some(ref scratch_dir) = self.scratch_dir {
+            let _ = fs::remove_dir_all(scratch_dir);
+        }
+        if let Some((path, replay)) = self.recording {
+            let file = File::create(path)?;
+            serde_json::to_writer(BufWriter::new(file), &replay).map_err(json_error)?;
+        }
+        Ok(())
+    }
+}
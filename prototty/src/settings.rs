@@ -0,0 +1,152 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+const AUTOSAVE_CHOICES_MS: &'static [u64] = &[5000, 10000, 30000, 60000];
+const GAME_OVER_CHOICES_MS: &'static [u64] = &[500, 1000, 2000, 3000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnimationSpeed {
+    Slow,
+    Normal,
+    Fast,
+    Fastest,
+}
+
+impl AnimationSpeed {
+    fn next(self) -> Self {
+        match self {
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Fastest,
+            AnimationSpeed::Fastest => AnimationSpeed::Slow,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AnimationSpeed::Slow => "Slow",
+            AnimationSpeed::Normal => "Normal",
+            AnimationSpeed::Fast => "Fast",
+            AnimationSpeed::Fastest => "Fastest",
+        }
+    }
+
+    // how much animation time passes in a period of real time
+    pub fn scale(self, period: Duration) -> Duration {
+        match self {
+            AnimationSpeed::Slow => period / 2,
+            AnimationSpeed::Normal => period,
+            AnimationSpeed::Fast => period * 2,
+            AnimationSpeed::Fastest => period * 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Glyphs {
+    Unicode,
+    Ascii,
+}
+
+impl Glyphs {
+    fn next(self) -> Self {
+        match self {
+            Glyphs::Unicode => Glyphs::Ascii,
+            Glyphs::Ascii => Glyphs::Unicode,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Glyphs::Unicode => "Unicode",
+            Glyphs::Ascii => "ASCII",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    AnimationSpeed,
+    Glyphs,
+    Autosave,
+    GameOverDelay,
+}
+
+fn next_choice(choices: &[u64], current: u64) -> u64 {
+    choices
+        .iter()
+        .cloned()
+        .find(|&choice| choice > current)
+        .unwrap_or(choices[0])
+}
+
+fn write_seconds(millis: u64, string: &mut String) {
+    if millis % 1000 == 0 {
+        write!(string, "{}s", millis / 1000).unwrap();
+    } else {
+        write!(string, "{}.{}s", millis / 1000, (millis % 1000) / 100).unwrap();
+    }
+}
+
+// settings missing from an older settings file take their defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub animation_speed: AnimationSpeed,
+    pub glyphs: Glyphs,
+    pub autosave_ms: u64,
+    pub game_over_ms: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            animation_speed: AnimationSpeed::Normal,
+            glyphs: Glyphs::Unicode,
+            autosave_ms: 10000,
+            game_over_ms: 1000,
+        }
+    }
+}
+
+impl Settings {
+    pub fn cycle(&mut self, setting: Setting) {
+        match setting {
+            Setting::AnimationSpeed => self.animation_speed = self.animation_speed.next(),
+            Setting::Glyphs => self.glyphs = self.glyphs.next(),
+            Setting::Autosave => {
+                self.autosave_ms = next_choice(AUTOSAVE_CHOICES_MS, self.autosave_ms)
+            }
+            Setting::GameOverDelay => {
+                self.game_over_ms = next_choice(GAME_OVER_CHOICES_MS, self.game_over_ms)
+            }
+        }
+    }
+
+    pub fn label(&self, setting: Setting) -> String {
+        let mut label = String::new();
+        match setting {
+            Setting::AnimationSpeed => {
+                write!(label, "Animation: {}", self.animation_speed.name()).unwrap()
+            }
+            Setting::Glyphs => write!(label, "Glyphs: {}", self.glyphs.name()).unwrap(),
+            Setting::Autosave => {
+                label.push_str("Autosave: ");
+                write_seconds(self.autosave_ms, &mut label);
+            }
+            Setting::GameOverDelay => {
+                label.push_str("Game over: ");
+                write_seconds(self.game_over_ms, &mut label);
+            }
+        }
+        label
+    }
+
+    pub fn autosave_period(&self) -> Duration {
+        Duration::from_millis(self.autosave_ms)
+    }
+
+    pub fn game_over_delay(&self) -> Duration {
+        Duration::from_millis(self.game_over_ms)
+    }
+}
@@ -113,8 +113,11 @@ impl Animation {
             AnimationState::TemporaryEntity(prototype, remaining) => {
                 let id = prototype.instantiate(messages);
 
+                // a long period (e.g. from a faster animation speed) shouldn't underflow
+                let remaining = remaining.checked_sub(period).unwrap_or_default();
+
                 messages.animate(Animation::new(
-                    AnimationState::RemoveEntity(id, remaining - period),
+                    AnimationState::RemoveEntity(id, remaining),
                     self.channel,
                 ));
 
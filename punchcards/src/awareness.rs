@@ -0,0 +1,78 @@
+use grid_2d::Coord;
+use entity_store::SpatialHashTable;
+
+const VIEW_DISTANCE: i32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Awareness {
+    Unaware,
+    Suspicious(Coord),
+    Alerted(Coord),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Noise {
+    pub origin: Coord,
+    pub volume: u32,
+}
+
+impl Noise {
+    pub fn is_audible_from(self, coord: Coord) -> bool {
+        let delta = coord - self.origin;
+        ((delta.x.abs() + delta.y.abs()) as u32) <= self.volume
+    }
+}
+
+impl Awareness {
+    pub fn look(
+        self,
+        coord: Coord,
+        player_coord: Coord,
+        spatial_hash: &SpatialHashTable,
+    ) -> Self {
+        if can_see(spatial_hash, coord, player_coord) {
+            return Awareness::Alerted(player_coord);
+        }
+        match self {
+            Awareness::Alerted(last_seen) => Awareness::Suspicious(last_seen),
+            Awareness::Suspicious(target) if target == coord => Awareness::Unaware,
+            other => other,
+        }
+    }
+
+    pub fn hear(self, noise: Noise, coord: Coord) -> Self {
+        match self {
+            Awareness::Alerted(_) => self,
+            _ => {
+                if noise.is_audible_from(coord) {
+                    Awareness::Suspicious(noise.origin)
+                } else {
+                    self
+                }
+            }
+        }
+    }
+}
+
+pub fn can_see(spatial_hash: &SpatialHashTable, from: Coord, to: Coord) -> bool {
+    let delta = to - from;
+    if delta.x * delta.x + delta.y * delta.y > VIEW_DISTANCE * VIEW_DISTANCE {
+        return false;
+    }
+    let steps = delta.x.abs().max(delta.y.abs());
+    for i in 1..steps {
+        let ratio = i as f64 / steps as f64;
+        let coord = Coord::new(
+            from.x + (delta.x as f64 * ratio).round() as i32,
+            from.y + (delta.y as f64 * ratio).round() as i32,
+        );
+        let opaque = spatial_hash
+            .get(coord)
+            .map(|cell| cell.solid_count > 0)
+            .unwrap_or(true);
+        if opaque {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,97 @@
+use rand::Rng;
+use direction::CardinalDirection;
+use entity_store::*;
+use pathfinding::{self, PathfindingContext};
+use intent::Intent;
+use message_queues::PushMessages;
+use script::{ScriptId, ScriptLibrary, ScriptView};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Behaviour {
+    Stationary,
+    Chase,
+    KeepDistance(u32),
+    FleeAtLowHitPoints(i32),
+    Patrol(CardinalDirection),
+    Boss,
+    Script(ScriptId),
+}
+
+impl Behaviour {
+    pub fn plan<M, R>(
+        self,
+        id: EntityId,
+        entity_store: &EntityStore,
+        spatial_hash: &SpatialHashTable,
+        pathfinding: &mut PathfindingContext,
+        scripts: &ScriptLibrary,
+        rng: &mut R,
+        messages: &mut M,
+    ) -> Option<Intent>
+    where
+        M: PushMessages,
+        R: Rng,
+    {
+        let coord = entity_store
+            .coord
+            .get(&id)
+            .cloned()
+            .expect("Entity missing coord");
+
+        let direction = match self {
+            Behaviour::Stationary => None,
+            Behaviour::Chase => pathfinding.chase(coord, spatial_hash),
+            Behaviour::KeepDistance(distance) => {
+                if let Some(direction) = pathfinding::find_player_in_line(spatial_hash, coord) {
+                    return Some(Intent::Shoot(direction));
+                }
+                match pathfinding.distance_to_player(coord) {
+                    Some(current) if current < distance => pathfinding.flee(coord, spatial_hash),
+                    Some(current) if current > distance => pathfinding.chase(coord, spatial_hash),
+                    _ => None,
+                }
+            }
+            Behaviour::FleeAtLowHitPoints(threshold) => {
+                let hit_points = entity_store.hit_points.get(&id).cloned();
+                if hit_points.map(|h| h <= threshold).unwrap_or(false) {
+                    pathfinding.flee(coord, spatial_hash)
+                } else {
+                    pathfinding.chase(coord, spatial_hash)
+                }
+            }
+            Behaviour::Patrol(direction) => {
+                if pathfinding::can_enter(spatial_hash, coord + direction.coord()) {
+                    Some(direction)
+                } else {
+                    // turn around, and start walking back next turn
+                    messages.change(insert::behaviour(
+                        id,
+                        Behaviour::Patrol(direction.opposite()),
+                    ));
+                    None
+                }
+            }
+            Behaviour::Boss => {
+                return entity_store.boss.get(&id).cloned().and_then(|boss| {
+                    boss.plan(id, coord, spatial_hash, pathfinding, messages)
+                });
+            }
+            Behaviour::Script(script_id) => {
+                let view = ScriptView {
+                    id,
+                    direction: None,
+                    entity_store,
+                    spatial_hash,
+                };
+                return scripts.get(script_id).plan(script_id, &view, rng).map(
+                    |(plan, intent)| {
+                        messages.change(insert::script_plan(id, plan));
+                        intent
+                    },
+                );
+            }
+        };
+
+        direction.map(|direction| Intent::towards(coord, direction, spatial_hash))
+    }
+}
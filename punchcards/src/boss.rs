@@ -0,0 +1,85 @@
+use entity_store::*;
+use pathfinding::{self, PathfindingContext};
+use intent::Intent;
+use message_queues::PushMessages;
+
+pub const MAX_HIT_POINTS: i32 = 12;
+
+const PHASE_THRESHOLDS: [i32; 2] = [8, 4];
+const CHARGE_COOLDOWN: u32 = 2;
+const SUMMON_COOLDOWN: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Boss {
+    pub phase: u32,
+    pub cooldown: u32,
+}
+
+impl Boss {
+    pub fn new() -> Self {
+        Self {
+            phase: 0,
+            cooldown: 0,
+        }
+    }
+
+    pub fn phase_for_hit_points(hit_points: i32) -> u32 {
+        PHASE_THRESHOLDS
+            .iter()
+            .filter(|&&threshold| hit_points <= threshold)
+            .count() as u32
+    }
+
+    pub fn plan<M>(
+        self,
+        id: EntityId,
+        coord: Coord,
+        spatial_hash: &SpatialHashTable,
+        pathfinding: &mut PathfindingContext,
+        messages: &mut M,
+    ) -> Option<Intent>
+    where
+        M: PushMessages,
+    {
+        if self.cooldown > 0 {
+            messages.change(insert::boss(
+                id,
+                Boss {
+                    cooldown: self.cooldown - 1,
+                    ..self
+                },
+            ));
+        } else {
+            if self.phase >= 2 && Intent::Summon.is_valid(coord, spatial_hash) {
+                messages.change(insert::boss(
+                    id,
+                    Boss {
+                        cooldown: SUMMON_COOLDOWN,
+                        ..self
+                    },
+                ));
+                return Some(Intent::Summon);
+            }
+
+            if self.phase >= 1 {
+                if let Some(direction) = pathfinding::find_player_in_line(spatial_hash, coord) {
+                    // adjacent players are punched rather than charged
+                    if pathfinding.distance_to_player(coord) > Some(1) {
+                        messages.change(insert::boss(
+                            id,
+                            Boss {
+                                cooldown: CHARGE_COOLDOWN,
+                                ..self
+                            },
+                        ));
+                        return Some(Intent::Charge(direction));
+                    }
+                }
+            }
+        }
+
+        pathfinding
+            .chase(coord, spatial_hash)
+            .map(|direction| Intent::towards(coord, direction, spatial_hash))
+    }
+}
@@ -0,0 +1,134 @@
+use rand::{Rng, SeedableRng, StdRng};
+use direction::CardinalDirection;
+use input::Input;
+use card_state::HandIndex;
+use state::{InputState, State};
+use pathfinding::{PathfindingContext, CARDINAL_DIRECTIONS};
+use event::ExternalEvent;
+
+const DEPTH_SCORE: i64 = 1000;
+const CARD_SCORE: i64 = 20;
+const NPC_SCORE: i64 = 5;
+const STAIRS_DISTANCE_SCORE: i64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Play(HandIndex, CardinalDirection),
+    Wait,
+}
+
+impl Action {
+    pub fn inputs(self) -> Vec<Input> {
+        match self {
+            Action::Play(index, direction) => {
+                vec![Input::SelectCard(index), Input::Direction(direction)]
+            }
+            Action::Wait => vec![Input::Wait],
+        }
+    }
+}
+
+pub trait Bot {
+    fn act(&mut self, state: &State) -> Action;
+}
+
+// every action the player could take from this state, legal or not
+pub fn candidate_actions(state: &State) -> Vec<Action> {
+    let mut actions = vec![Action::Wait];
+    for (index, card) in state.card_state().hand.iter().enumerate() {
+        if card.is_some() {
+            for &direction in CARDINAL_DIRECTIONS.iter() {
+                actions.push(Action::Play(index, direction));
+            }
+        }
+    }
+    actions
+}
+
+// plays the action on a copy of the state, returning None if the game rejected it
+pub fn simulate(state: &State, action: Action) -> Option<(State, Option<ExternalEvent>)> {
+    let mut state = state.clone();
+    let event = state.headless_turn(action.inputs());
+    if let InputState::WaitingForDirection(..) = *state.input_state() {
+        return None;
+    }
+    Some((state, event))
+}
+
+fn stairs_distance(state: &State) -> Option<u32> {
+    let player_coord = state.player_coord()?;
+    let mut pathfinding = PathfindingContext::new(state.size());
+    pathfinding.update_player_map(player_coord, state.spatial_hash());
+    let entity_store = state.entity_store();
+    entity_store
+        .stairs
+        .iter()
+        .filter_map(|id| entity_store.coord.get(id))
+        .filter_map(|&coord| pathfinding.distance_to_player(coord))
+        .min()
+}
+
+pub fn score(state: &State, event: &Option<ExternalEvent>) -> i64 {
+    match event {
+        &Some(ExternalEvent::GameOver) => return i64::min_value(),
+        &Some(ExternalEvent::Victory) | &Some(ExternalEvent::TutorialComplete) => {
+            return i64::max_value()
+        }
+        &Some(ExternalEvent::BossPhaseChanged(_)) | &None => (),
+    }
+
+    let card_state = state.card_state();
+    let num_cards = card_state.hand.iter().filter(|card| card.is_some()).count()
+        + card_state.deck.num_cards();
+    let num_npcs = state.entity_store().npc.iter().count();
+
+    let stairs_distance = stairs_distance(state).unwrap_or(0) as i64;
+
+    state.depth() as i64 * DEPTH_SCORE + num_cards as i64 * CARD_SCORE
+        - num_npcs as i64 * NPC_SCORE - stairs_distance * STAIRS_DISTANCE_SCORE
+}
+
+// tries every card in every direction and picks whichever leaves the best looking state
+pub struct GreedyBot;
+
+impl Bot for GreedyBot {
+    fn act(&mut self, state: &State) -> Action {
+        let mut best = (Action::Wait, i64::min_value());
+        for action in candidate_actions(state) {
+            // waiting never costs a card, so it's only worth doing when nothing can be played
+            if action == Action::Wait {
+                continue;
+            }
+            if let Some((next, event)) = simulate(state, action) {
+                let score = score(&next, &event);
+                if score > best.1 {
+                    best = (action, score);
+                }
+            }
+        }
+        best.0
+    }
+}
+
+// plays a random legal card, as a baseline for the greedy bot
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: usize) -> Self {
+        Self {
+            rng: StdRng::from_seed(&[seed]),
+        }
+    }
+}
+
+impl Bot for RandomBot {
+    fn act(&mut self, state: &State) -> Action {
+        let legal = candidate_actions(state)
+            .into_iter()
+            .filter(|&action| action != Action::Wait && simulate(state, action).is_some())
+            .collect::<Vec<_>>();
+        self.rng.choose(&legal).cloned().unwrap_or(Action::Wait)
+    }
+}
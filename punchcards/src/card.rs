@@ -1,26 +1,42 @@
+use rand::Rng;
 use entity_store::*;
 use direction::CardinalDirection;
 use common_animations;
 use message_queues::PushMessages;
 use prototypes;
+use script::{ScriptId, ScriptLibrary, ScriptMessages, ScriptView};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Card {
     Move,
     Punch,
     Shoot,
+    Scripted(ScriptId),
 }
 
 impl Card {
-    pub fn play<M>(
+    pub fn noise(self, scripts: &ScriptLibrary) -> u32 {
+        match self {
+            Card::Move => 1,
+            Card::Punch => 3,
+            Card::Shoot => 8,
+            Card::Scripted(script_id) => scripts.get(script_id).noise(),
+        }
+    }
+
+    pub fn play<M, R>(
         self,
         entity_id: EntityId,
         entity_store: &EntityStore,
+        spatial_hash: &SpatialHashTable,
         direction: CardinalDirection,
         id_allocator: &mut EntityIdAllocator,
+        scripts: &ScriptLibrary,
+        rng: &mut R,
         messages: &mut M,
     ) where
         M: PushMessages,
+        R: Rng,
     {
         match self {
             Card::Move => {
@@ -44,6 +60,19 @@ impl Card {
                 prototypes::bullet(bullet_id, start_coord, direction, messages);
                 common_animations::bullet(bullet_id, messages);
             }
+            Card::Scripted(script_id) => {
+                let view = ScriptView {
+                    id: entity_id,
+                    direction: Some(direction),
+                    entity_store,
+                    spatial_hash,
+                };
+                let mut messages = ScriptMessages {
+                    id_allocator,
+                    messages,
+                };
+                scripts.get(script_id).run(&view, rng, &mut messages);
+            }
         }
     }
 }
@@ -100,15 +100,21 @@ impl CardState {
         hand_size: usize,
         rng: &mut R,
     ) -> Self {
-        let discard_deck = Deck::new();
-        let mut deck = Deck::from_cards(cards);
-        deck.shuffle(rng);
-        let hand = Hand::new(hand_size);
+        let mut state = Self {
+            hand: Hand::new(hand_size),
+            deck: Deck::from_cards(cards),
+            discard_deck: Deck::new(),
+        };
+        state.deck.shuffle(rng);
+        state.fill_hand();
+        state
+    }
 
+    pub fn unshuffled<I: IntoIterator<Item = Card>>(cards: I, hand_size: usize) -> Self {
         let mut state = Self {
-            hand,
-            deck,
-            discard_deck,
+            hand: Hand::new(hand_size),
+            deck: Deck::from_cards(cards),
+            discard_deck: Deck::new(),
         };
 
         state.fill_hand();
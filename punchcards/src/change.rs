@@ -27,8 +27,13 @@ impl ChangeContext {
         swap_messages: &mut MessageQueuesSwap,
         rng: &mut R,
     ) -> Option<Event> {
+        let mut champion_defeated = false;
+
         loop {
             for id in messages.removed_entities.drain(..) {
+                if world.entity_store.champion.contains(&id) {
+                    champion_defeated = true;
+                }
                 for change in world.entity_components.remove_entity(id) {
                     messages.changes.push(change);
                 }
@@ -65,6 +70,7 @@ impl ChangeContext {
 
             for (id, card) in messages.take_cards.drain(..) {
                 card_state.deck.add_random(card, rng);
+                messages.gameplay_events.push(GameplayEvent::CardTaken(card));
                 messages.removed_entities.push(id);
             }
         };
@@ -73,11 +79,14 @@ impl ChangeContext {
             world.id_allocator.free(id);
         }
 
-        if card_state.hand.is_empty() {
+        if champion_defeated {
+            Some(Event::External(ExternalEvent::Victory))
+        } else if card_state.hand.is_empty() {
             Some(Event::External(ExternalEvent::GameOver))
-        } else if messages.next_level {
-            messages.next_level = false;
-            Some(Event::NextLevel)
+        } else if let Some(phase) = messages.boss_phase.take() {
+            Some(Event::External(ExternalEvent::BossPhaseChanged(phase)))
+        } else if let Some(change) = messages.level_change.take() {
+            Some(Event::ChangeLevel(change))
         } else {
             None
         }
@@ -28,3 +28,10 @@ pub fn bullet<M: PushMessages>(
 ) {
     slide(id, Duration::from_millis(timing::BULLET_SLIDE_MILLIS), messages);
 }
+
+pub fn charge<M: PushMessages>(
+    id: EntityId,
+    messages: &mut M,
+) {
+    slide(id, Duration::from_millis(timing::CHARGE_SLIDE_MILLIS), messages);
+}
@@ -0,0 +1,541 @@
+use std::fmt;
+use std::str::FromStr;
+use card::Card;
+use tile::Tile;
+use levels;
+use terrain;
+use validate::{self, Arrival, Exit, LevelError};
+use script::{ParseErrorKind, Script};
+use super::*;
+
+const ORDER_FILE: &'static str = "order";
+const MANIFEST_FILE: &'static str = "manifest";
+const DEFAULT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackErrorKind {
+    MissingFile,
+    MissingName,
+    DuplicatePack(String),
+    MissingRequirement(String),
+    UnknownDirective(String),
+    MissingField(&'static str),
+    InvalidNumber(String),
+    InvalidGlyph(String),
+    InvalidColour(String),
+    GlyphInUse(char),
+    UnknownTile(String),
+    DepthOutOfRange(usize),
+    InvalidLevel(LevelError),
+    Script(ParseErrorKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackError {
+    pub file: String,
+    pub line: usize,
+    pub kind: PackErrorKind,
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {:?}", self.file, self.kind)
+        } else {
+            write!(f, "{}:{}: {:?}", self.file, self.line, self.kind)
+        }
+    }
+}
+
+type FieldResult<T> = Result<T, PackErrorKind>;
+
+fn field<'a, I: Iterator<Item = &'a str>>(tokens: &mut I, name: &'static str) -> FieldResult<&'a str> {
+    tokens.next().ok_or(PackErrorKind::MissingField(name))
+}
+
+fn number<T: FromStr>(token: &str) -> FieldResult<T> {
+    token
+        .parse()
+        .map_err(|_| PackErrorKind::InvalidNumber(token.to_string()))
+}
+
+fn glyph(token: &str) -> FieldResult<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if !ch.is_whitespace() => Ok(ch),
+        _ => Err(PackErrorKind::InvalidGlyph(token.to_string())),
+    }
+}
+
+fn colour(token: &str) -> FieldResult<[u8; 3]> {
+    let invalid = || PackErrorKind::InvalidColour(token.to_string());
+    if token.len() != 6 {
+        return Err(invalid());
+    }
+    let mut colour = [0; 3];
+    for (i, channel) in colour.iter_mut().enumerate() {
+        let digits = token.get(i * 2..i * 2 + 2).ok_or_else(invalid)?;
+        *channel = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+    }
+    Ok(colour)
+}
+
+fn tile(token: &str) -> FieldResult<Tile> {
+    let tile = match token {
+        "Player" => Tile::Player,
+        "Wall" => Tile::Wall,
+        "Floor" => Tile::Floor,
+        "CardMove" => Tile::CardMove,
+        "CardSpecial" => Tile::CardSpecial,
+        "TargetDummy" => Tile::TargetDummy,
+        "SmallRobot" => Tile::SmallRobot,
+        "EliteRobot" => Tile::EliteRobot,
+        "GunRobot" => Tile::GunRobot,
+        "Guard" => Tile::Guard,
+        "Skirmisher" => Tile::Skirmisher,
+        "Stairs" => Tile::Stairs,
+        "UpStairs" => Tile::UpStairs,
+        "Bullet" => Tile::Bullet,
+        other => return Err(PackErrorKind::UnknownTile(other.to_string())),
+    };
+    Ok(tile)
+}
+
+struct Loader<F> {
+    read: F,
+    content: Content,
+}
+
+impl<F> Loader<F>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn read(&mut self, path: &str) -> Result<String, PackError> {
+        (self.read)(path).ok_or_else(|| PackError {
+            file: path.to_string(),
+            line: 0,
+            kind: PackErrorKind::MissingFile,
+        })
+    }
+
+    fn script(&mut self, name: &str, path: &str) -> Result<ScriptId, PackError> {
+        let source = self.read(path)?;
+        let script = Script::parse(&source).map_err(|error| PackError {
+            file: path.to_string(),
+            line: error.line,
+            kind: PackErrorKind::Script(error.kind),
+        })?;
+        Ok(self.content.scripts.add(name.to_string(), script))
+    }
+
+    fn level(&mut self, depth: usize, path: &str) -> Result<(), PackError> {
+        // blank lines are skipped, so each row remembers the line it came from
+        let (lines, rows): (Vec<usize>, Vec<String>) = self.read(path)?
+            .lines()
+            .map(str::trim_right)
+            .enumerate()
+            .filter(|&(_, row)| !row.is_empty())
+            .map(|(index, row)| (index + 1, row.to_string()))
+            .unzip();
+        let arrival = Arrival::for_depth(depth);
+        let exit = Exit::for_depth(depth, levels::FINAL_DEPTH);
+        validate::validate_level(&rows, arrival, exit, &self.content).map_err(|error| {
+            PackError {
+                file: path.to_string(),
+                line: error.row().and_then(|row| lines.get(row).cloned()).unwrap_or(0),
+                kind: PackErrorKind::InvalidLevel(error),
+            }
+        })?;
+        self.content.levels.insert(depth, rows);
+        Ok(())
+    }
+
+    fn pack(&mut self, directory: &str) -> Result<(), PackError> {
+        let manifest_path = format!("{}/{}", directory, MANIFEST_FILE);
+        let manifest = self.read(&manifest_path)?;
+
+        let mut name = None;
+        let mut version = DEFAULT_VERSION;
+        let mut levels = Vec::new();
+
+        for (index, line) in manifest.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |kind| PackError {
+                file: manifest_path.clone(),
+                line: line_number,
+                kind,
+            };
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut tokens = line.split_whitespace();
+            let directive = if let Some(directive) = tokens.next() {
+                directive
+            } else {
+                continue;
+            };
+            let path = |file: &str| format!("{}/{}", directory, file);
+            match directive {
+                "name" => {
+                    let pack_name = field(&mut tokens, "name").map_err(&error)?;
+                    if self.content.packs.iter().any(|pack| pack.name == pack_name) {
+                        return Err(error(PackErrorKind::DuplicatePack(pack_name.to_string())));
+                    }
+                    name = Some(pack_name.to_string());
+                }
+                "version" => {
+                    version = field(&mut tokens, "version")
+                        .and_then(number)
+                        .map_err(&error)?;
+                }
+                "requires" => {
+                    let required = field(&mut tokens, "pack").map_err(&error)?;
+                    if !self.content.packs.iter().any(|pack| pack.name == required) {
+                        return Err(error(PackErrorKind::MissingRequirement(required.to_string())));
+                    }
+                }
+                "card" => {
+                    let card_name = field(&mut tokens, "name").map_err(&error)?;
+                    let file = field(&mut tokens, "script").map_err(&error)?;
+                    let copies = match tokens.next() {
+                        Some(token) => number(token).map_err(&error)?,
+                        None => 1,
+                    };
+                    let script_id = self.script(card_name, &path(file))?;
+                    for _ in 0..copies {
+                        self.content.cards.push(Card::Scripted(script_id));
+                    }
+                }
+                "enemy" => {
+                    let enemy_name = field(&mut tokens, "name").map_err(&error)?;
+                    let file = field(&mut tokens, "script").map_err(&error)?;
+                    let hit_points = field(&mut tokens, "hit points")
+                        .and_then(number)
+                        .map_err(&error)?;
+                    let enemy_glyph = field(&mut tokens, "glyph")
+                        .and_then(glyph)
+                        .map_err(&error)?;
+                    let enemy_colour = field(&mut tokens, "colour")
+                        .and_then(colour)
+                        .map_err(&error)?;
+                    if terrain::GLYPHS.contains(enemy_glyph)
+                        || self.content.enemy_by_glyph(enemy_glyph).is_some()
+                    {
+                        return Err(error(PackErrorKind::GlyphInUse(enemy_glyph)));
+                    }
+                    let script = self.script(enemy_name, &path(file))?;
+                    self.content.enemies.push(EnemyPrototype {
+                        name: enemy_name.to_string(),
+                        glyph: enemy_glyph,
+                        hit_points,
+                        script,
+                        style: TileStyle {
+                            glyph: enemy_glyph,
+                            colour: enemy_colour,
+                        },
+                    });
+                }
+                "level" => {
+                    let depth = field(&mut tokens, "depth")
+                        .and_then(number)
+                        .map_err(&error)?;
+                    if depth > levels::FINAL_DEPTH {
+                        return Err(error(PackErrorKind::DepthOutOfRange(depth)));
+                    }
+                    let file = field(&mut tokens, "file").map_err(&error)?;
+                    levels.push((depth, path(file)));
+                }
+                "tile" => {
+                    let styled = field(&mut tokens, "tile").and_then(tile).map_err(&error)?;
+                    let tile_glyph = field(&mut tokens, "glyph")
+                        .and_then(glyph)
+                        .map_err(&error)?;
+                    let tile_colour = field(&mut tokens, "colour")
+                        .and_then(colour)
+                        .map_err(&error)?;
+                    self.content.tile_styles.push((
+                        styled,
+                        TileStyle {
+                            glyph: tile_glyph,
+                            colour: tile_colour,
+                        },
+                    ));
+                }
+                other => return Err(error(PackErrorKind::UnknownDirective(other.to_string()))),
+            }
+        }
+
+        // levels are checked once the whole manifest is read, so they may use any of its enemies
+        for (depth, path) in levels {
+            self.level(depth, &path)?;
+        }
+
+        let name = name.ok_or_else(|| PackError {
+            file: manifest_path.clone(),
+            line: 0,
+            kind: PackErrorKind::MissingName,
+        })?;
+        self.content.packs.push(PackId { name, version });
+
+        Ok(())
+    }
+}
+
+pub fn load<F>(mut read: F) -> Result<Content, PackError>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let order = if let Some(order) = read(ORDER_FILE) {
+        order
+    } else {
+        return Ok(Content::builtin());
+    };
+
+    let mut loader = Loader {
+        read,
+        content: Content::builtin(),
+    };
+
+    for line in order.lines() {
+        let directory = line.split('#').next().unwrap_or("").trim();
+        if !directory.is_empty() {
+            loader.pack(directory)?;
+        }
+    }
+
+    Ok(loader.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use grid_2d::Coord;
+    use super::*;
+
+    const SCRIPT: &'static str = "move forward";
+    const LEVEL: &'static str = "#####\n#<.>#\n#####\n";
+
+    // loads the packs listed in order from a set of in-memory files
+    fn load_files(files: &[(&str, &str)]) -> Result<Content, PackError> {
+        let files = files
+            .iter()
+            .map(|&(path, contents)| (path.to_string(), contents.to_string()))
+            .collect::<HashMap<_, _>>();
+        load(|path| files.get(path).cloned())
+    }
+
+    fn load_manifest(manifest: &str) -> Result<Content, PackError> {
+        load_files(&[
+            ("order", "pack"),
+            ("pack/manifest", manifest),
+            ("pack/a.script", SCRIPT),
+            ("pack/level", LEVEL),
+        ])
+    }
+
+    fn manifest_error(manifest: &str) -> (usize, PackErrorKind) {
+        let error = load_manifest(manifest).unwrap_err();
+        assert_eq!(error.file, "pack/manifest");
+        (error.line, error.kind)
+    }
+
+    #[test]
+    fn no_order_file_means_builtin_content() {
+        let content = load_files(&[]).unwrap();
+        assert!(content.packs().is_empty());
+        assert!(content.extra_cards().is_empty());
+    }
+
+    #[test]
+    fn name_and_version() {
+        let content = load_manifest("name first\nversion 3").unwrap();
+        assert_eq!(
+            content.packs(),
+            &[PackId {
+                name: "first".to_string(),
+                version: 3,
+            }]
+        );
+        let content = load_manifest("name first").unwrap();
+        assert_eq!(content.packs()[0].version, DEFAULT_VERSION);
+    }
+
+    #[test]
+    fn a_pack_needs_a_name() {
+        let error = load_manifest("version 2").unwrap_err();
+        assert_eq!(error.line, 0);
+        assert_eq!(error.kind, PackErrorKind::MissingName);
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let error = load_files(&[
+            ("order", "a\nb"),
+            ("a/manifest", "name same"),
+            ("b/manifest", "name same"),
+        ]).unwrap_err();
+        assert_eq!(error.file, "b/manifest");
+        assert_eq!(error.kind, PackErrorKind::DuplicatePack("same".to_string()));
+    }
+
+    #[test]
+    fn requires() {
+        let content = load_files(&[
+            ("order", "base\nextra"),
+            ("base/manifest", "name base"),
+            ("extra/manifest", "name extra\nrequires base"),
+        ]).unwrap();
+        assert_eq!(content.packs().len(), 2);
+
+        // packs are loaded in order, so a requirement must come first
+        let error = load_files(&[
+            ("order", "extra\nbase"),
+            ("base/manifest", "name base"),
+            ("extra/manifest", "name extra\nrequires base"),
+        ]).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.kind, PackErrorKind::MissingRequirement("base".to_string()));
+    }
+
+    #[test]
+    fn card() {
+        let content = load_manifest("name p\ncard Lunge a.script 2").unwrap();
+        assert_eq!(content.extra_cards().len(), 2);
+        let script = content.scripts().find("Lunge").expect("No card script");
+        assert_eq!(content.extra_cards()[0], Card::Scripted(script));
+
+        let content = load_manifest("name p\ncard Lunge a.script").unwrap();
+        assert_eq!(content.extra_cards().len(), 1);
+
+        assert_eq!(
+            manifest_error("name p\ncard Lunge"),
+            (2, PackErrorKind::MissingField("script"))
+        );
+        assert_eq!(
+            manifest_error("name p\ncard Lunge a.script many"),
+            (2, PackErrorKind::InvalidNumber("many".to_string()))
+        );
+        let error = load_manifest("name p\ncard Lunge missing.script").unwrap_err();
+        assert_eq!(error.file, "pack/missing.script");
+        assert_eq!(error.kind, PackErrorKind::MissingFile);
+    }
+
+    #[test]
+    fn enemy() {
+        let content = load_manifest("name p\nenemy Drone a.script 3 d ff8000").unwrap();
+        let index = content.enemy_by_glyph('d').expect("No enemy");
+        let enemy = content.enemy(index);
+        assert_eq!(enemy.name, "Drone");
+        assert_eq!(enemy.hit_points, 3);
+        assert_eq!(enemy.style.colour, [0xff, 0x80, 0x00]);
+
+        assert_eq!(
+            manifest_error("name p\nenemy Drone a.script 3 dd ff8000"),
+            (2, PackErrorKind::InvalidGlyph("dd".to_string()))
+        );
+        assert_eq!(
+            manifest_error("name p\nenemy Drone a.script 3 d orange"),
+            (2, PackErrorKind::InvalidColour("orange".to_string()))
+        );
+        assert_eq!(
+            manifest_error("name p\nenemy Drone a.script 3 E ff8000"),
+            (2, PackErrorKind::GlyphInUse('E'))
+        );
+        assert_eq!(
+            manifest_error("name p\nenemy Drone a.script"),
+            (2, PackErrorKind::MissingField("hit points"))
+        );
+    }
+
+    #[test]
+    fn enemy_script_errors_point_at_the_script() {
+        let error = load_files(&[
+            ("order", "pack"),
+            ("pack/manifest", "name p\nenemy Drone bad.script 3 d ff8000"),
+            ("pack/bad.script", "move forward\nfly"),
+        ]).unwrap_err();
+        assert_eq!(error.file, "pack/bad.script");
+        assert_eq!(error.line, 2);
+        assert_eq!(
+            error.kind,
+            PackErrorKind::Script(ParseErrorKind::UnknownCommand("fly".to_string()))
+        );
+    }
+
+    #[test]
+    fn level() {
+        let content = load_manifest("name p\nlevel 1 level").unwrap();
+        assert_eq!(
+            content.level(1),
+            Some(&["#####".to_string(), "#<.>#".to_string(), "#####".to_string()][..])
+        );
+
+        let depth = levels::FINAL_DEPTH + 1;
+        assert_eq!(
+            manifest_error(&format!("name p\nlevel {} level", depth)),
+            (2, PackErrorKind::DepthOutOfRange(depth))
+        );
+        assert_eq!(
+            manifest_error("name p\nlevel 1"),
+            (2, PackErrorKind::MissingField("file"))
+        );
+    }
+
+    #[test]
+    fn levels_may_use_enemies_declared_after_them() {
+        let content = load_files(&[
+            ("order", "pack"),
+            ("pack/manifest", "name p\nlevel 1 level\nenemy Drone a.script 3 d ff8000"),
+            ("pack/a.script", SCRIPT),
+            ("pack/level", "######\n#<d.>#\n######"),
+        ]).unwrap();
+        assert!(content.level(1).is_some());
+    }
+
+    #[test]
+    fn level_errors_point_at_the_line_in_the_level_file() {
+        let error = load_files(&[
+            ("order", "pack"),
+            ("pack/manifest", "name p\nlevel 1 level"),
+            ("pack/level", "#####\n\n#<.>#\n#...#\n####."),
+        ]).unwrap_err();
+        assert_eq!(error.file, "pack/level");
+        assert_eq!(error.line, 5);
+        assert_eq!(
+            error.kind,
+            PackErrorKind::InvalidLevel(LevelError::OpenBorder(Coord::new(4, 3)))
+        );
+    }
+
+    #[test]
+    fn tile() {
+        let content = load_manifest("name p\ntile Wall % 808080").unwrap();
+        assert_eq!(
+            content.tile_style(Tile::Wall),
+            Some(TileStyle {
+                glyph: '%',
+                colour: [0x80, 0x80, 0x80],
+            })
+        );
+        assert_eq!(
+            manifest_error("name p\ntile Lava ~ ff0000"),
+            (2, PackErrorKind::UnknownTile("Lava".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert_eq!(
+            manifest_error("name p\n\n# a comment\nspawn Drone"),
+            (4, PackErrorKind::UnknownDirective("spawn".to_string()))
+        );
+    }
+
+    #[test]
+    fn errors_report_the_manifest_line() {
+        assert_eq!(
+            manifest_error("# header\nname p\n\nversion two"),
+            (4, PackErrorKind::InvalidNumber("two".to_string()))
+        );
+        let error = load_manifest("name p\nversion two").unwrap_err();
+        assert_eq!(error.to_string(), "pack/manifest:2: InvalidNumber(\"two\")");
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use card::Card;
+use tile::Tile;
+use script::{ScriptId, ScriptLibrary};
+
+mod load;
+
+pub use self::load::{PackError, PackErrorKind};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackId {
+    pub name: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileStyle {
+    pub glyph: char,
+    pub colour: [u8; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnemyPrototype {
+    pub name: String,
+    pub glyph: char,
+    pub hit_points: i32,
+    pub script: ScriptId,
+    pub style: TileStyle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Content {
+    packs: Vec<PackId>,
+    scripts: ScriptLibrary,
+    cards: Vec<Card>,
+    enemies: Vec<EnemyPrototype>,
+    levels: BTreeMap<usize, Vec<String>>,
+    tile_styles: Vec<(Tile, TileStyle)>,
+}
+
+impl Content {
+    pub fn builtin() -> Self {
+        Self {
+            packs: Vec::new(),
+            scripts: ScriptLibrary::builtin(),
+            cards: Vec::new(),
+            enemies: Vec::new(),
+            levels: BTreeMap::new(),
+            tile_styles: Vec::new(),
+        }
+    }
+
+    // reads files relative to the pack directory, starting with the "order" file
+    pub fn load<F>(read: F) -> Result<Self, PackError>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        load::load(read)
+    }
+
+    pub fn with_level(mut self, depth: usize, rows: Vec<String>) -> Self {
+        self.levels.insert(depth, rows);
+        self
+    }
+
+    pub fn packs(&self) -> &[PackId] {
+        &self.packs
+    }
+
+    pub fn scripts(&self) -> &ScriptLibrary {
+        &self.scripts
+    }
+
+    pub fn extra_cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn enemies(&self) -> &[EnemyPrototype] {
+        &self.enemies
+    }
+
+    pub fn enemy(&self, index: u32) -> &EnemyPrototype {
+        &self.enemies[index as usize]
+    }
+
+    pub fn enemy_by_glyph(&self, glyph: char) -> Option<u32> {
+        self.enemies
+            .iter()
+            .position(|enemy| enemy.glyph == glyph)
+            .map(|index| index as u32)
+    }
+
+    pub fn level(&self, depth: usize) -> Option<&[String]> {
+        self.levels.get(&depth).map(Vec::as_slice)
+    }
+
+    pub fn tile_style(&self, tile: Tile) -> Option<TileStyle> {
+        if let Tile::PackEnemy(index) = tile {
+            return Some(self.enemy(index).style);
+        }
+        // later packs take priority over earlier ones
+        self.tile_styles
+            .iter()
+            .rev()
+            .find(|&&(styled, _)| styled == tile)
+            .map(|&(_, style)| style)
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use message_queues::MessageQueues;
+use world::{World, WorldSaveState};
+use levels::{self, LevelSet};
+use content::Content;
+use terrain::TerrainType;
+use validate::{self, Arrival, Exit};
+
+const MAX_GENERATION_ATTEMPTS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelChange {
+    Down,
+    Up,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dungeon {
+    level_set: LevelSet,
+    seed: usize,
+    depth: usize,
+    // each world counts its own turns, so the whole run's turns are counted here
+    turns: u64,
+    levels: BTreeMap<usize, World>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DungeonSaveState {
+    level_set: LevelSet,
+    seed: usize,
+    depth: usize,
+    #[serde(default)]
+    turns: u64,
+    levels: BTreeMap<usize, WorldSaveState>,
+}
+
+impl Dungeon {
+    pub fn new(level_set: LevelSet, seed: usize) -> Self {
+        Self {
+            level_set,
+            seed,
+            depth: 0,
+            turns: 0,
+            levels: BTreeMap::new(),
+        }
+    }
+
+    // a dungeon holding no levels, for showing a single level at its depth
+    pub fn at_depth(level_set: LevelSet, depth: usize) -> Self {
+        Self {
+            depth,
+            ..Self::new(level_set, 0)
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn level_set(&self) -> LevelSet {
+        self.level_set
+    }
+
+    pub fn turns(&self) -> u64 {
+        self.turns
+    }
+
+    pub fn count_turn(&mut self) {
+        self.turns += 1;
+    }
+
+    pub fn num_floors(&self) -> usize {
+        self.level_set.final_depth() + 1
+    }
+
+    pub fn next_depth(&self, change: LevelChange) -> Option<usize> {
+        match change {
+            LevelChange::Down => {
+                if self.depth < self.level_set.final_depth() {
+                    Some(self.depth + 1)
+                } else {
+                    None
+                }
+            }
+            LevelChange::Up => self.depth.checked_sub(1),
+        }
+    }
+
+    pub fn enter(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    // takes a visited level out of the dungeon, or builds it on the first visit
+    pub fn level(&mut self, depth: usize, content: &Content, messages: &mut MessageQueues) -> World {
+        if let Some(world) = self.levels.remove(&depth) {
+            world
+        } else {
+            self.build_level(depth, content, messages)
+        }
+    }
+
+    fn build_level(&self, depth: usize, content: &Content, messages: &mut MessageQueues) -> World {
+        let final_depth = self.level_set.final_depth();
+        let arrival = Arrival::for_depth(depth);
+        let exit = Exit::for_depth(depth, final_depth);
+        let mut attempt = 0;
+        loop {
+            let seed = self.seed.wrapping_add(attempt);
+            let terrain = levels::terrain(self.level_set, depth, seed, content);
+            let world = World::new(&terrain, content, messages);
+            match validate::validate_world(&world, arrival, exit) {
+                Ok(()) => return world,
+                Err(_) => {
+                    // generated levels get another try with a different seed
+                    let generated = match terrain {
+                        TerrainType::Generated { .. } => true,
+                        _ => false,
+                    };
+                    if !generated || attempt >= MAX_GENERATION_ATTEMPTS {
+                        // a plain room keeps the run going when no valid level can be made
+                        let terrain = levels::fallback(depth, final_depth);
+                        return World::new(&terrain, content, messages);
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    pub fn store(&mut self, depth: usize, world: World) {
+        self.levels.insert(depth, world);
+    }
+
+    pub fn save(&self) -> DungeonSaveState {
+        DungeonSaveState {
+            level_set: self.level_set,
+            seed: self.seed,
+            depth: self.depth,
+            turns: self.turns,
+            levels: self.levels
+                .iter()
+                .map(|(&depth, world)| (depth, world.save()))
+                .collect(),
+        }
+    }
+}
+
+impl From<DungeonSaveState> for Dungeon {
+    fn from(
+        DungeonSaveState {
+            level_set,
+            seed,
+            depth,
+            turns,
+            levels,
+        }: DungeonSaveState,
+    ) -> Self {
+        Self {
+            level_set,
+            seed,
+            depth,
+            turns,
+            levels: levels
+                .into_iter()
+                .map(|(depth, world)| (depth, World::from(world)))
+                .collect(),
+        }
+    }
+}
@@ -1,8 +1,21 @@
+use dungeon::LevelChange;
+use card::Card;
+
 pub enum ExternalEvent {
     GameOver,
+    Victory,
+    BossPhaseChanged(u32),
+    TutorialComplete,
 }
 
 pub enum Event {
     External(ExternalEvent),
-    NextLevel,
+    ChangeLevel(LevelChange),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameplayEvent {
+    CardTaken(Card),
+    NpcDamaged,
+    NpcDestroyed,
 }
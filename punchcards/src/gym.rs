@@ -0,0 +1,203 @@
+use grid_2d::Coord;
+use card::Card;
+use state::{self, State};
+use bot::Action;
+use pathfinding::CARDINAL_DIRECTIONS;
+use event::{ExternalEvent, GameplayEvent};
+use terrain;
+
+// the largest level there can be; smaller levels are padded with walls
+pub const GRID_WIDTH: usize = terrain::MAX_WIDTH as usize;
+pub const GRID_HEIGHT: usize = terrain::MAX_HEIGHT as usize;
+
+pub const WALL_CHANNEL: usize = 0;
+pub const NPC_CHANNEL: usize = 1;
+pub const CARD_CHANNEL: usize = 2;
+pub const PLAYER_CHANNEL: usize = 3;
+pub const STAIRS_CHANNEL: usize = 4;
+pub const UP_STAIRS_CHANNEL: usize = 5;
+pub const NUM_CHANNELS: usize = 6;
+
+pub const HAND_SIZE: usize = state::INITIAL_HAND_SIZE;
+
+// the last action waits, all others play a card in a direction
+pub const NUM_ACTIONS: usize = HAND_SIZE * 4 + 1;
+pub const WAIT_ACTION: usize = NUM_ACTIONS - 1;
+
+const VICTORY_REWARD: f32 = 10.0;
+const GAME_OVER_REWARD: f32 = -1.0;
+const DEPTH_REWARD: f32 = 1.0;
+const CARD_TAKEN_REWARD: f32 = 0.1;
+const NPC_DESTROYED_REWARD: f32 = 0.1;
+const ILLEGAL_ACTION_REWARD: f32 = -0.1;
+
+// 0 is an empty slot, scripted cards follow the built in ones
+pub fn card_code(card: Option<Card>) -> u32 {
+    match card {
+        None => 0,
+        Some(Card::Move) => 1,
+        Some(Card::Punch) => 2,
+        Some(Card::Shoot) => 3,
+        Some(Card::Scripted(script_id)) => 4 + script_id.index() as u32,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Observation {
+    // NUM_CHANNELS planes of GRID_HEIGHT rows of GRID_WIDTH cells, each 0 or 1
+    pub grid: Vec<u8>,
+    // one card_code per hand slot
+    pub hand: Vec<u32>,
+    pub deck_count: u32,
+    pub depth: u32,
+    // whether each action would currently be accepted
+    pub legal_actions: Vec<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+}
+
+pub fn action(index: usize) -> Option<Action> {
+    if index == WAIT_ACTION {
+        Some(Action::Wait)
+    } else if index < WAIT_ACTION {
+        Some(Action::Play(index / 4, CARDINAL_DIRECTIONS[index % 4]))
+    } else {
+        None
+    }
+}
+
+fn legal_actions(state: &State) -> Vec<bool> {
+    (0..NUM_ACTIONS)
+        .map(|index| match action(index) {
+            Some(Action::Play(hand_index, direction)) => state.can_play(hand_index, direction),
+            Some(Action::Wait) => true,
+            None => false,
+        })
+        .collect()
+}
+
+fn observe(state: &State) -> Observation {
+    let mut grid = vec![0; NUM_CHANNELS * GRID_WIDTH * GRID_HEIGHT];
+    let spatial_hash = state.spatial_hash();
+    for y in 0..GRID_HEIGHT {
+        for x in 0..GRID_WIDTH {
+            let index = |channel: usize| (channel * GRID_HEIGHT + y) * GRID_WIDTH + x;
+            match spatial_hash.get(Coord::new(x as i32, y as i32)) {
+                Some(cell) => {
+                    let channels = [
+                        (WALL_CHANNEL, cell.solid_count > 0),
+                        (NPC_CHANNEL, !cell.npc_set.is_empty()),
+                        (CARD_CHANNEL, !cell.card_set.is_empty()),
+                        (PLAYER_CHANNEL, cell.player_count > 0),
+                        (STAIRS_CHANNEL, cell.stairs_count > 0),
+                        (UP_STAIRS_CHANNEL, cell.up_stairs_count > 0),
+                    ];
+                    for &(channel, present) in channels.iter() {
+                        if present {
+                            grid[index(channel)] = 1;
+                        }
+                    }
+                }
+                None => grid[index(WALL_CHANNEL)] = 1,
+            }
+        }
+    }
+
+    let card_state = state.card_state();
+    let mut hand = card_state
+        .hand
+        .iter()
+        .map(|card| card_code(*card))
+        .collect::<Vec<_>>();
+    hand.resize(HAND_SIZE, 0);
+
+    Observation {
+        grid,
+        hand,
+        deck_count: card_state.deck.num_cards() as u32,
+        depth: state.depth() as u32,
+        legal_actions: legal_actions(state),
+    }
+}
+
+pub struct Environment {
+    state: State,
+    done: bool,
+}
+
+impl Environment {
+    pub fn new(seed: usize) -> Self {
+        Self {
+            state: State::new(seed),
+            done: false,
+        }
+    }
+
+    pub fn reset(&mut self, seed: usize) -> Observation {
+        self.state = State::new(seed);
+        self.done = false;
+        observe(&self.state)
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn step(&mut self, action_index: usize) -> Step {
+        if self.done {
+            return Step {
+                observation: observe(&self.state),
+                reward: 0.0,
+                done: true,
+            };
+        }
+
+        let action = match action_index {
+            index if index < NUM_ACTIONS && legal_actions(&self.state)[index] => action(index),
+            _ => None,
+        };
+        let action = if let Some(action) = action {
+            action
+        } else {
+            return Step {
+                observation: observe(&self.state),
+                reward: ILLEGAL_ACTION_REWARD,
+                done: false,
+            };
+        };
+
+        let depth = self.state.depth();
+        let event = self.state.headless_turn(action.inputs());
+
+        let mut reward = (self.state.depth() as f32 - depth as f32) * DEPTH_REWARD;
+        for gameplay_event in self.state.gameplay_events() {
+            reward += match *gameplay_event {
+                GameplayEvent::CardTaken(_) => CARD_TAKEN_REWARD,
+                GameplayEvent::NpcDestroyed => NPC_DESTROYED_REWARD,
+                GameplayEvent::NpcDamaged => 0.0,
+            };
+        }
+        match event {
+            Some(ExternalEvent::Victory) => {
+                reward += VICTORY_REWARD;
+                self.done = true;
+            }
+            Some(ExternalEvent::GameOver) => {
+                reward += GAME_OVER_REWARD;
+                self.done = true;
+            }
+            _ => (),
+        }
+
+        Step {
+            observation: observe(&self.state),
+            reward,
+            done: self.done,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use direction::CardinalDirection;
+use entity_store::*;
+use pathfinding::{self, CARDINAL_DIRECTIONS};
+use prototypes;
+use common_animations;
+use message_queues::PushMessages;
+
+const MAX_SUMMONS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Intent {
+    Move(CardinalDirection),
+    Attack(CardinalDirection),
+    Shoot(CardinalDirection),
+    Charge(CardinalDirection),
+    Summon,
+}
+
+fn for_each_line_cell<F>(
+    coord: Coord,
+    direction: CardinalDirection,
+    spatial_hash: &SpatialHashTable,
+    mut f: F,
+) where
+    F: FnMut(Coord),
+{
+    let delta = direction.coord();
+    let mut target = coord + delta;
+    while let Some(cell) = spatial_hash.get(target) {
+        if cell.solid_count > 0 {
+            break;
+        }
+        f(target);
+        target = target + delta;
+    }
+}
+
+fn for_each_summon_cell<F>(coord: Coord, spatial_hash: &SpatialHashTable, mut f: F)
+where
+    F: FnMut(Coord),
+{
+    CARDINAL_DIRECTIONS
+        .iter()
+        .map(|direction| coord + direction.coord())
+        .filter(|&target| pathfinding::can_enter(spatial_hash, target))
+        .take(MAX_SUMMONS)
+        .for_each(|target| f(target));
+}
+
+impl Intent {
+    pub fn towards(coord: Coord, direction: CardinalDirection, spatial_hash: &SpatialHashTable) -> Self {
+        let target = coord + direction.coord();
+        let attack = spatial_hash
+            .get(target)
+            .map(|cell| cell.player_count > 0)
+            .unwrap_or(false);
+        if attack {
+            Intent::Attack(direction)
+        } else {
+            Intent::Move(direction)
+        }
+    }
+
+    pub fn is_valid(self, coord: Coord, spatial_hash: &SpatialHashTable) -> bool {
+        match self {
+            Intent::Move(direction) => {
+                pathfinding::can_enter(spatial_hash, coord + direction.coord())
+            }
+            Intent::Attack(direction) => spatial_hash
+                .get(coord + direction.coord())
+                .map(|cell| cell.player_count > 0)
+                .unwrap_or(false),
+            Intent::Shoot(direction) | Intent::Charge(direction) => {
+                pathfinding::player_in_line(spatial_hash, coord, direction)
+            }
+            Intent::Summon => {
+                let mut any = false;
+                for_each_summon_cell(coord, spatial_hash, |_| any = true);
+                any
+            }
+        }
+    }
+
+    pub fn for_each_cell<F>(self, coord: Coord, spatial_hash: &SpatialHashTable, mut f: F)
+    where
+        F: FnMut(Coord),
+    {
+        match self {
+            Intent::Move(direction) | Intent::Attack(direction) => f(coord + direction.coord()),
+            Intent::Shoot(direction) | Intent::Charge(direction) => {
+                for_each_line_cell(coord, direction, spatial_hash, f)
+            }
+            Intent::Summon => for_each_summon_cell(coord, spatial_hash, f),
+        }
+    }
+
+    pub fn execute<M>(
+        self,
+        id: EntityId,
+        entity_store: &EntityStore,
+        spatial_hash: &SpatialHashTable,
+        id_allocator: &mut EntityIdAllocator,
+        messages: &mut M,
+    ) where
+        M: PushMessages,
+    {
+        let coord = entity_store
+            .coord
+            .get(&id)
+            .cloned()
+            .expect("Entity missing coord");
+        match self {
+            Intent::Move(direction) | Intent::Attack(direction) => {
+                messages.change(insert::coord(id, coord + direction.coord()));
+            }
+            Intent::Shoot(direction) => {
+                let bullet_id = id_allocator.allocate();
+                prototypes::bullet(bullet_id, coord + direction.coord(), direction, messages);
+                common_animations::bullet(bullet_id, messages);
+            }
+            Intent::Charge(direction) => {
+                messages.change(insert::slide_direction(id, direction));
+                common_animations::charge(id, messages);
+            }
+            Intent::Summon => {
+                for_each_summon_cell(coord, spatial_hash, |target| {
+                    prototypes::summoned_robot(id_allocator.allocate(), target, messages);
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use terrain::TerrainType;
+use content::Content;
+
+pub const FINAL_DEPTH: usize = 4;
+pub const TUTORIAL_FINAL_DEPTH: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelSet {
+    Standard,
+    Tutorial,
+    Playtest,
+}
+
+impl LevelSet {
+    pub fn final_depth(self) -> usize {
+        match self {
+            LevelSet::Standard => FINAL_DEPTH,
+            LevelSet::Tutorial => TUTORIAL_FINAL_DEPTH,
+            LevelSet::Playtest => 0,
+        }
+    }
+}
+
+pub fn terrain(level_set: LevelSet, depth: usize, seed: usize, content: &Content) -> TerrainType {
+    match level_set {
+        LevelSet::Standard => match content.level(depth) {
+            Some(rows) => TerrainType::Text(rows.to_vec()),
+            None => standard(depth, seed),
+        },
+        LevelSet::Tutorial => tutorial(depth),
+        LevelSet::Playtest => {
+            TerrainType::Text(content.level(0).expect("No level to playtest").to_vec())
+        }
+    }
+}
+
+// a room with the stairs a level at this depth needs, for when its real level is invalid
+pub fn fallback(depth: usize, final_depth: usize) -> TerrainType {
+    let arrival = if depth == 0 { '@' } else { '<' };
+    let exit = if depth < final_depth { '>' } else { '.' };
+    TerrainType::Text(vec![
+        "#######".to_string(),
+        format!("#{}...{}#", arrival, exit),
+        "#.....#".to_string(),
+        "#######".to_string(),
+    ])
+}
+
+fn tutorial(depth: usize) -> TerrainType {
+    match depth {
+        0 => TerrainType::StaticStrings(vec![
+            "#######",
+            "#@.m..#",
+            "#####.#",
+            "#>.0..#",
+            "#######",
+        ]),
+        _ => TerrainType::StaticStrings(vec![
+            "#########",
+            "#<....1.#",
+            "#.......#",
+            "#.......#",
+            "#########",
+        ]),
+    }
+}
+
+fn standard(depth: usize, seed: usize) -> TerrainType {
+    match depth {
+        0 => TerrainType::StaticStrings(vec![
+            "##########",
+            "#@.>.....#",
+            "##..1.#..#",
+            "#.....#..#",
+            "#.....#..#",
+            "###.###..#",
+            "#........#",
+            "#........#",
+            "#........#",
+            "##########",
+        ]),
+        FINAL_DEPTH => TerrainType::StaticStrings(vec![
+            "###############",
+            "#<............#",
+            "#.............#",
+            "#...#.....#...#",
+            "#.............#",
+            "#......C......#",
+            "#.............#",
+            "#...#.....#...#",
+            "#.1.........1.#",
+            "###############",
+        ]),
+        _ => TerrainType::Generated { seed, depth },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use message_queues::MessageQueues;
+    use validate::{self, Arrival, Exit};
+    use world::World;
+    use super::*;
+
+    #[test]
+    fn fallback_levels_are_valid_at_every_depth() {
+        let content = Content::builtin();
+        for depth in 0..FINAL_DEPTH + 1 {
+            let world = World::new(
+                &fallback(depth, FINAL_DEPTH),
+                &content,
+                &mut MessageQueues::new(),
+            );
+            let arrival = Arrival::for_depth(depth);
+            let exit = Exit::for_depth(depth, FINAL_DEPTH);
+            assert_eq!(validate::validate_world(&world, arrival, exit), Ok(()), "{}", depth);
+        }
+    }
+}
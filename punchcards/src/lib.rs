@@ -26,6 +26,10 @@ mod prototypes;
 mod common_animations;
 mod animation;
 mod pathfinding;
+mod dungeon;
+mod levels;
+mod tutorial;
+mod validate;
 
 mod event;
 pub mod card_state;
@@ -34,5 +38,15 @@ pub mod tile;
 pub mod tile_info;
 pub mod state;
 pub mod card;
+pub mod behaviour;
+pub mod intent;
+pub mod awareness;
+pub mod boss;
+pub mod script;
+pub mod content;
+pub mod bot;
+pub mod gym;
 
-pub use event::ExternalEvent;
+pub use event::{ExternalEvent, GameplayEvent};
+pub use validate::{validate_level, Arrival, Exit, LevelError};
+pub use terrain::{MAX_HEIGHT, MAX_WIDTH};
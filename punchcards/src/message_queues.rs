@@ -1,6 +1,9 @@
 use entity_store::*;
 use animation::*;
 use card::*;
+use awareness::Noise;
+use dungeon::LevelChange;
+use event::GameplayEvent;
 
 macro_rules! swap_drain {
     ($field:ident, $current:expr, $swap:expr) => {
@@ -33,7 +36,10 @@ pub struct MessageQueues {
     pub removed_entities: Vec<EntityId>,
     pub take_cards: Vec<(EntityId, Card)>,
     pub player_moved_to: Option<Coord>,
-    pub next_level: bool,
+    pub noise: Option<Noise>,
+    pub level_change: Option<LevelChange>,
+    pub boss_phase: Option<u32>,
+    pub gameplay_events: Vec<GameplayEvent>,
 }
 
 impl MessageQueues {
@@ -44,7 +50,10 @@ impl MessageQueues {
             removed_entities: Vec::new(),
             take_cards: Vec::new(),
             player_moved_to: None,
-            next_level: false,
+            noise: None,
+            level_change: None,
+            boss_phase: None,
+            gameplay_events: Vec::new(),
         }
     }
 }
@@ -55,7 +64,9 @@ pub trait PushMessages {
     fn remove(&mut self, entity_id: EntityId);
     fn take_card(&mut self, entity_id: EntityId, card: Card);
     fn move_player(&mut self, coord: Coord);
-    fn next_level(&mut self);
+    fn change_level(&mut self, change: LevelChange);
+    fn boss_phase_changed(&mut self, phase: u32);
+    fn gameplay_event(&mut self, event: GameplayEvent);
 }
 
 impl PushMessages for MessageQueues {
@@ -74,7 +85,13 @@ impl PushMessages for MessageQueues {
     fn move_player(&mut self, coord: Coord) {
         self.player_moved_to = Some(coord);
     }
-    fn next_level(&mut self) {
-        self.next_level = true;
+    fn change_level(&mut self, change: LevelChange) {
+        self.level_change = Some(change);
+    }
+    fn boss_phase_changed(&mut self, phase: u32) {
+        self.boss_phase = Some(phase);
+    }
+    fn gameplay_event(&mut self, event: GameplayEvent) {
+        self.gameplay_events.push(event);
     }
 }
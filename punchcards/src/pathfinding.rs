@@ -2,7 +2,13 @@ use std::cmp::Ordering;
 use grid_search::*;
 use entity_store::*;
 use direction::*;
-use message_queues::PushMessages;
+
+pub const CARDINAL_DIRECTIONS: [CardinalDirection; 4] = [
+    CardinalDirection::North,
+    CardinalDirection::East,
+    CardinalDirection::South,
+    CardinalDirection::West,
+];
 
 struct SpatialHashSolidCellGrid<'a>(&'a SpatialHashTable);
 struct SpatialHashSolidOrOccupiedCellGrid<'a>(&'a SpatialHashTable);
@@ -21,6 +27,59 @@ impl<'a> SolidGrid for SpatialHashSolidOrOccupiedCellGrid<'a> {
     }
 }
 
+pub fn can_enter(spatial_hash: &SpatialHashTable, coord: Coord) -> bool {
+    SpatialHashSolidOrOccupiedCellGrid(spatial_hash).is_solid(coord) == Some(false)
+}
+
+pub fn player_in_line(
+    spatial_hash: &SpatialHashTable,
+    coord: Coord,
+    direction: CardinalDirection,
+) -> bool {
+    let mut coord = coord + direction.coord();
+    while let Some(cell) = spatial_hash.get(coord) {
+        if cell.player_count > 0 {
+            return true;
+        }
+        if cell.solid_count > 0 || !cell.npc_set.is_empty() {
+            return false;
+        }
+        coord = coord + direction.coord();
+    }
+    false
+}
+
+pub fn find_player_in_line(
+    spatial_hash: &SpatialHashTable,
+    coord: Coord,
+) -> Option<CardinalDirection> {
+    CARDINAL_DIRECTIONS
+        .iter()
+        .cloned()
+        .find(|&direction| player_in_line(spatial_hash, coord, direction))
+}
+
+pub fn step_towards(
+    spatial_hash: &SpatialHashTable,
+    coord: Coord,
+    target: Coord,
+) -> Option<CardinalDirection> {
+    let distance = |a: Coord| {
+        let delta = target - a;
+        delta.x.abs() + delta.y.abs()
+    };
+    let mut best_distance = distance(coord);
+    let mut best_direction = None;
+    for &direction in CARDINAL_DIRECTIONS.iter() {
+        let next = coord + direction.coord();
+        if can_enter(spatial_hash, next) && distance(next) < best_distance {
+            best_distance = distance(next);
+            best_direction = Some(direction);
+        }
+    }
+    best_direction
+}
+
 #[derive(Debug, Clone)]
 pub struct PathfindingContext {
     search: SearchContext<u32>,
@@ -50,21 +109,11 @@ impl PathfindingContext {
             .expect("Failed to update player map");
     }
 
-    pub fn act<M>(
+    pub fn chase(
         &mut self,
-        id: EntityId,
-        entity_store: &EntityStore,
+        coord: Coord,
         spatial_hash: &SpatialHashTable,
-        messages: &mut M,
-    ) where
-        M: PushMessages,
-    {
-        let coord = entity_store
-            .coord
-            .get(&id)
-            .cloned()
-            .expect("Entity missing coord");
-
+    ) -> Option<CardinalDirection> {
         let cell = self.distance_map
             .get(coord)
             .cell()
@@ -88,16 +137,46 @@ impl PathfindingContext {
         );
 
         match result {
-            Ok(_) => {
-                if let Some(direction) = self.path.iter().next() {
-                    let delta = direction.coord();
-                    let new = coord + delta;
-                    messages.change(insert::coord(id, new));
+            Ok(_) => self.path
+                .iter()
+                .next()
+                .map(|direction| CardinalDirection::from_unit_coord(direction.coord())),
+            Err(Error::NoPath) => None,
+            Err(e) => panic!("Unexpected pathfinding error: {:?}", e),
+        }
+    }
+
+    pub fn flee(
+        &self,
+        coord: Coord,
+        spatial_hash: &SpatialHashTable,
+    ) -> Option<CardinalDirection> {
+        let mut best_cost = if let Some(cell) = self.distance_map.get(coord).cell() {
+            cell.cost()
+        } else {
+            return None;
+        };
+
+        let mut best_direction = None;
+
+        for &direction in CARDINAL_DIRECTIONS.iter() {
+            let next = coord + direction.coord();
+            if !can_enter(spatial_hash, next) {
+                continue;
+            }
+            if let Some(cell) = self.distance_map.get(next).cell() {
+                if cell.cost() > best_cost {
+                    best_cost = cell.cost();
+                    best_direction = Some(direction);
                 }
             }
-            Err(Error::NoPath) => (),
-            Err(e) => panic!("Unexpected pathfinding error: {:?}", e),
         }
+
+        best_direction
+    }
+
+    pub fn distance_to_player(&self, coord: Coord) -> Option<u32> {
+        self.distance_map.get(coord).cell().map(|cell| cell.cost())
     }
 
     pub fn sort_entities_by_distance_to_player(
@@ -1,8 +1,11 @@
 use entity_store::*;
-use tile_info;
+use tile::Tile;
+use boss::Boss;
 use direction::CardinalDirection;
 use common_animations;
 use message_queues::PushMessages;
+use dungeon::LevelChange;
+use event::GameplayEvent;
 
 pub fn precheck<'a, I: IntoIterator<Item = &'a EntityChange>>(
     changes: I,
@@ -67,15 +70,26 @@ where
                     return false;
                 }
 
-                if sh_cell.solid_count > 0 && entity_store.bullet.contains(&id) {
+                if (sh_cell.solid_count > 0 || sh_cell.player_count > 0)
+                    && entity_store.bullet.contains(&id)
+                {
                     messages.remove(id);
                     return false;
                 }
 
                 let is_npc = entity_store.npc.contains(&id);
 
-                if is_npc && dest_npc.is_some() {
-                    // npcs can't move through one another
+                let blocked = dest_npc.is_some() || sh_cell.solid_count > 0;
+
+                if is_npc && (blocked || sh_cell.player_count > 0)
+                    && entity_store.slide_direction.get(&id).is_some()
+                {
+                    // a charging npc stops at the first thing it hits
+                    messages.change(EntityChange::Remove(id, ComponentType::SlideDirection));
+                }
+
+                if is_npc && blocked {
+                    // npcs can't move through one another or through walls
                     return false;
                 }
 
@@ -107,7 +121,9 @@ where
 
                 if is_player {
                     if sh_cell.stairs_count > 0 {
-                        messages.next_level();
+                        messages.change_level(LevelChange::Down);
+                    } else if sh_cell.up_stairs_count > 0 {
+                        messages.change_level(LevelChange::Up);
                     } else {
                         messages.move_player(coord);
                     }
@@ -115,15 +131,33 @@ where
             }
         }
         &Insert(id, HitPoints(hit_points)) => {
-            if hit_points == 0 {
+            let damaged = entity_store
+                .hit_points
+                .get(&id)
+                .map(|&current| hit_points < current)
+                .unwrap_or(false);
+            if damaged && entity_store.npc.contains(&id) {
+                messages.gameplay_event(GameplayEvent::NpcDamaged);
+                if hit_points <= 0 {
+                    messages.gameplay_event(GameplayEvent::NpcDestroyed);
+                }
+            }
+            if hit_points <= 0 {
                 messages.remove(id);
-            } else if hit_points == 1 {
-                if let Some(tile_info) = entity_store.tile_info.get(&id) {
-                    let tile_info = tile_info::TileInfo {
-                        damaged: true,
-                        ..*tile_info
-                    };
-                    messages.change(insert::tile_info(id, tile_info));
+            } else if let Some(tile_info) = entity_store.tile_info.get(&id) {
+                let mut next_tile_info = *tile_info;
+                // healing can take an npc back out of its damaged look
+                next_tile_info.damaged = hit_points == 1;
+                if let Some(boss) = entity_store.boss.get(&id) {
+                    let phase = Boss::phase_for_hit_points(hit_points);
+                    if phase > boss.phase {
+                        messages.change(insert::boss(id, Boss { phase, ..*boss }));
+                        messages.boss_phase_changed(phase);
+                        next_tile_info.tile = Tile::Champion(phase);
+                    }
+                }
+                if next_tile_info != *tile_info {
+                    messages.change(insert::tile_info(id, next_tile_info));
                 }
             }
         }
@@ -4,6 +4,11 @@ use grid_2d::Coord;
 use tile::Tile;
 use tile_info::TileInfo;
 use card::Card;
+use behaviour::Behaviour;
+use awareness::Awareness;
+use boss::{self, Boss};
+use script;
+use content::EnemyPrototype;
 use message_queues::PushMessages;
 
 const FLOOR_DEPTH: i32      = 1;
@@ -75,10 +80,17 @@ pub fn punch<M: PushMessages>(
     ));
 }
 
+// npcs remember how many hit points they started with, so healing can't go past it
+fn hit_points<M: PushMessages>(id: EntityId, hit_points: i32, messages: &mut M) {
+    messages.change(insert::hit_points(id, hit_points));
+    messages.change(insert::max_hit_points(id, hit_points));
+}
+
 pub fn target_dummy<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
     messages.change(insert::coord(id, coord));
     messages.change(insert::npc(id));
-    messages.change(insert::hit_points(id, 2));
+    hit_points(id, 2, messages);
+    messages.change(insert::behaviour(id, Behaviour::Stationary));
     messages.change(insert::tile_info(
         id,
         TileInfo::new(Tile::TargetDummy, NPC_DEPTH),
@@ -88,13 +100,100 @@ pub fn target_dummy<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut
 pub fn small_robot<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
     messages.change(insert::coord(id, coord));
     messages.change(insert::npc(id));
-    messages.change(insert::hit_points(id, 2));
+    hit_points(id, 2, messages);
+    messages.change(insert::behaviour(id, Behaviour::Chase));
+    messages.change(insert::awareness(id, Awareness::Unaware));
     messages.change(insert::tile_info(
         id,
         TileInfo::new(Tile::SmallRobot, NPC_DEPTH),
     ));
 }
 
+pub fn elite_robot<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    hit_points(id, 4, messages);
+    messages.change(insert::behaviour(id, Behaviour::FleeAtLowHitPoints(1)));
+    messages.change(insert::awareness(id, Awareness::Unaware));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::EliteRobot, NPC_DEPTH),
+    ));
+}
+
+pub fn summoned_robot<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    small_robot(id, coord, messages);
+    // summoned robots already know where the player is
+    messages.change(EntityChange::Remove(id, ComponentType::Awareness));
+}
+
+pub fn gun_robot<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    hit_points(id, 2, messages);
+    messages.change(insert::behaviour(id, Behaviour::KeepDistance(3)));
+    messages.change(insert::awareness(id, Awareness::Unaware));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::GunRobot, NPC_DEPTH),
+    ));
+}
+
+pub fn guard<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    hit_points(id, 3, messages);
+    messages.change(insert::behaviour(id, Behaviour::Patrol(CardinalDirection::East)));
+    messages.change(insert::awareness(id, Awareness::Unaware));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::Guard, NPC_DEPTH),
+    ));
+}
+
+pub fn skirmisher<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    hit_points(id, 2, messages);
+    messages.change(insert::behaviour(id, Behaviour::Script(script::SKIRMISHER)));
+    messages.change(insert::awareness(id, Awareness::Unaware));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::Skirmisher, NPC_DEPTH),
+    ));
+}
+
+pub fn pack_enemy<M: PushMessages>(
+    id: EntityId,
+    coord: Coord,
+    index: u32,
+    enemy: &EnemyPrototype,
+    messages: &mut M,
+) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    hit_points(id, enemy.hit_points, messages);
+    messages.change(insert::behaviour(id, Behaviour::Script(enemy.script)));
+    messages.change(insert::awareness(id, Awareness::Unaware));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::PackEnemy(index), NPC_DEPTH),
+    ));
+}
+
+pub fn champion<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::npc(id));
+    messages.change(insert::champion(id));
+    messages.change(insert::boss(id, Boss::new()));
+    hit_points(id, boss::MAX_HIT_POINTS, messages);
+    messages.change(insert::behaviour(id, Behaviour::Boss));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::Champion(0), NPC_DEPTH),
+    ));
+}
+
 pub fn stairs<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
     messages.change(insert::coord(id, coord));
     messages.change(insert::stairs(id));
@@ -104,6 +203,15 @@ pub fn stairs<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
     ));
 }
 
+pub fn up_stairs<M: PushMessages>(id: EntityId, coord: Coord, messages: &mut M) {
+    messages.change(insert::coord(id, coord));
+    messages.change(insert::up_stairs(id));
+    messages.change(insert::tile_info(
+        id,
+        TileInfo::new(Tile::UpStairs, STAIRS_DEPTH),
+    ));
+}
+
 pub fn bullet<M: PushMessages>(id: EntityId, coord: Coord, direction: CardinalDirection, messages: &mut M) {
     messages.change(insert::slide_direction(id, direction));
     messages.change(insert::bullet(id));
@@ -0,0 +1,560 @@
+use rand::Rng;
+use direction::CardinalDirection;
+use entity_store::*;
+use intent::Intent;
+use awareness;
+use pathfinding::{self, CARDINAL_DIRECTIONS};
+use prototypes;
+use common_animations;
+use message_queues::PushMessages;
+
+mod parse;
+
+pub use self::parse::{ParseError, ParseErrorKind};
+
+const DEFAULT_NOISE: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScriptId(usize);
+
+impl ScriptId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+pub const JAB: ScriptId = ScriptId(0);
+pub const SKIRMISHER: ScriptId = ScriptId(1);
+
+const BUILTIN: &'static [(&'static str, &'static str)] = &[
+    ("Jab", include_str!("builtin/jab.script")),
+    ("Skirmisher", include_str!("builtin/skirmisher.script")),
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum Direction {
+    Absolute(CardinalDirection),
+    Forward,
+    Back,
+    TowardPlayer,
+    AwayFromPlayer,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum Comparison {
+    Below(i32),
+    Above(i32),
+}
+
+impl Comparison {
+    fn holds(self, value: i32) -> bool {
+        match self {
+            Comparison::Below(limit) => value < limit,
+            Comparison::Above(limit) => value > limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Condition {
+    Not(Box<Condition>),
+    PlayerInLine,
+    PlayerAdjacent,
+    PlayerVisible,
+    NpcAhead,
+    Chance(u32),
+    HitPoints(Comparison),
+    Distance(Comparison),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum Command {
+    Move(Direction),
+    Punch(Direction),
+    Shoot(Direction),
+    Charge(Direction),
+    Push(Direction),
+    Damage(Direction, i32),
+    Heal(i32),
+    Summon,
+}
+
+impl Command {
+    fn direction(self) -> Option<Direction> {
+        match self {
+            Command::Move(direction)
+            | Command::Punch(direction)
+            | Command::Shoot(direction)
+            | Command::Charge(direction)
+            | Command::Push(direction)
+            | Command::Damage(direction, _) => Some(direction),
+            Command::Heal(_) | Command::Summon => None,
+        }
+    }
+
+    // how a planned npc command is shown to the player before it happens
+    fn intent(
+        self,
+        coord: Coord,
+        direction: Option<CardinalDirection>,
+        spatial_hash: &SpatialHashTable,
+    ) -> Option<Intent> {
+        match (self, direction) {
+            (Command::Summon, _) => Some(Intent::Summon),
+            (Command::Shoot(_), Some(direction)) => Some(Intent::Shoot(direction)),
+            (Command::Charge(_), Some(direction)) => Some(Intent::Charge(direction)),
+            (_, Some(direction)) => Some(Intent::towards(coord, direction, spatial_hash)),
+            (_, None) => None,
+        }
+    }
+}
+
+// a rule with no commands waits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Rule {
+    conditions: Vec<Condition>,
+    commands: Vec<Command>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    noise: u32,
+    rules: Vec<Rule>,
+}
+
+// the rule a script chose, and the direction its first command was aimed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptPlan {
+    pub script: ScriptId,
+    rule: usize,
+    heading: Option<CardinalDirection>,
+}
+
+pub struct ScriptView<'a> {
+    pub id: EntityId,
+    pub direction: Option<CardinalDirection>,
+    pub entity_store: &'a EntityStore,
+    pub spatial_hash: &'a SpatialHashTable,
+}
+
+impl<'a> ScriptView<'a> {
+    fn coord(&self) -> Coord {
+        *self.entity_store
+            .coord
+            .get(&self.id)
+            .expect("Entity missing coord")
+    }
+
+    fn player_coord(&self) -> Option<Coord> {
+        self.entity_store
+            .player
+            .iter()
+            .next()
+            .and_then(|id| self.entity_store.coord.get(id))
+            .cloned()
+    }
+
+    fn distance_to_player(&self) -> Option<i32> {
+        self.player_coord().map(|player_coord| {
+            let delta = player_coord - self.coord();
+            delta.x.abs() + delta.y.abs()
+        })
+    }
+
+    fn condition<R: Rng>(&self, condition: &Condition, rng: &mut R) -> bool {
+        let coord = self.coord();
+        match condition {
+            &Condition::Not(ref condition) => !self.condition(condition, rng),
+            &Condition::PlayerInLine => {
+                pathfinding::find_player_in_line(self.spatial_hash, coord).is_some()
+            }
+            &Condition::PlayerAdjacent => self.distance_to_player() == Some(1),
+            &Condition::PlayerVisible => self.player_coord()
+                .map(|player_coord| awareness::can_see(self.spatial_hash, coord, player_coord))
+                .unwrap_or(false),
+            &Condition::NpcAhead => self.direction
+                .and_then(|direction| self.spatial_hash.get(coord + direction.coord()))
+                .map(|cell| !cell.npc_set.is_empty())
+                .unwrap_or(false),
+            &Condition::Chance(percent) => rng.gen_range(0, 100) < percent,
+            &Condition::HitPoints(comparison) => self.entity_store
+                .hit_points
+                .get(&self.id)
+                .map(|&hit_points| comparison.holds(hit_points))
+                .unwrap_or(false),
+            &Condition::Distance(comparison) => self.distance_to_player()
+                .map(|distance| comparison.holds(distance))
+                .unwrap_or(false),
+        }
+    }
+
+    fn direction<R: Rng>(&self, direction: Direction, rng: &mut R) -> Option<CardinalDirection> {
+        let coord = self.coord();
+        match direction {
+            Direction::Absolute(direction) => Some(direction),
+            Direction::Forward => self.direction,
+            Direction::Back => self.direction.map(CardinalDirection::opposite),
+            Direction::TowardPlayer => self.player_coord().and_then(|player_coord| {
+                pathfinding::find_player_in_line(self.spatial_hash, coord)
+                    .or_else(|| pathfinding::step_towards(self.spatial_hash, coord, player_coord))
+            }),
+            Direction::AwayFromPlayer => self.player_coord().and_then(|player_coord| {
+                let distance = |a: Coord| {
+                    let delta = player_coord - a;
+                    delta.x.abs() + delta.y.abs()
+                };
+                CARDINAL_DIRECTIONS
+                    .iter()
+                    .cloned()
+                    .find(|direction| {
+                        let next = coord + direction.coord();
+                        pathfinding::can_enter(self.spatial_hash, next)
+                            && distance(next) > distance(coord)
+                    })
+            }),
+            Direction::Random => rng.choose(&CARDINAL_DIRECTIONS).cloned(),
+        }
+    }
+}
+
+// scripts only read the world through a ScriptView, and change it through these messages
+pub struct ScriptMessages<'a, M: 'a + PushMessages> {
+    pub id_allocator: &'a mut EntityIdAllocator,
+    pub messages: &'a mut M,
+}
+
+fn npc_at(spatial_hash: &SpatialHashTable, coord: Coord) -> Option<EntityId> {
+    spatial_hash
+        .get(coord)
+        .and_then(|cell| cell.npc_set.iter().next().cloned())
+}
+
+impl<'a, M: PushMessages> ScriptMessages<'a, M> {
+    fn command<R: Rng>(
+        &mut self,
+        command: Command,
+        direction: Option<CardinalDirection>,
+        view: &ScriptView,
+        rng: &mut R,
+    ) {
+        let coord = view.coord();
+        let direction = match (command.direction(), direction) {
+            (Some(_), Some(direction)) => Some(direction),
+            (Some(direction), None) => match view.direction(direction, rng) {
+                Some(direction) => Some(direction),
+                // a command that can't be aimed does nothing
+                None => return,
+            },
+            (None, _) => None,
+        };
+        let target = direction.map(|direction| coord + direction.coord());
+        match (command, direction, target) {
+            (Command::Move(_), _, Some(target)) => {
+                self.messages.change(insert::coord(view.id, target));
+            }
+            (Command::Punch(_), Some(direction), Some(target)) => {
+                let punch_id = self.id_allocator.allocate();
+                common_animations::punch(punch_id, target, direction, self.messages);
+            }
+            (Command::Shoot(_), Some(direction), Some(target)) => {
+                let bullet_id = self.id_allocator.allocate();
+                prototypes::bullet(bullet_id, target, direction, self.messages);
+                common_animations::bullet(bullet_id, self.messages);
+            }
+            (Command::Charge(_), Some(direction), _) => {
+                self.messages
+                    .change(insert::slide_direction(view.id, direction));
+                common_animations::charge(view.id, self.messages);
+            }
+            (Command::Push(_), Some(direction), Some(target)) => {
+                if let Some(npc_id) = npc_at(view.spatial_hash, target) {
+                    self.messages
+                        .change(insert::coord(npc_id, target + direction.coord()));
+                }
+            }
+            (Command::Damage(_, amount), _, Some(target)) => {
+                if let Some(npc_id) = npc_at(view.spatial_hash, target) {
+                    if let Some(&hit_points) = view.entity_store.hit_points.get(&npc_id) {
+                        // hit points never go below 0
+                        let hit_points = (hit_points - amount).max(0);
+                        self.messages.change(insert::hit_points(npc_id, hit_points));
+                    }
+                }
+            }
+            (Command::Heal(amount), _, _) => {
+                let entity_store = view.entity_store;
+                let hit_points = entity_store.hit_points.get(&view.id);
+                let max_hit_points = entity_store.max_hit_points.get(&view.id);
+                if let (Some(&hit_points), Some(&max_hit_points)) = (hit_points, max_hit_points) {
+                    let healed = (hit_points + amount).min(max_hit_points);
+                    if healed > hit_points {
+                        self.messages.change(insert::hit_points(view.id, healed));
+                    }
+                }
+            }
+            (Command::Summon, _, _) => {
+                Intent::Summon.execute(
+                    view.id,
+                    view.entity_store,
+                    view.spatial_hash,
+                    self.id_allocator,
+                    self.messages,
+                );
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        parse::parse(source)
+    }
+
+    pub fn noise(&self) -> u32 {
+        self.noise
+    }
+
+    // the first rule whose conditions hold and whose first command can be aimed is chosen
+    fn choose<R: Rng>(
+        &self,
+        view: &ScriptView,
+        rng: &mut R,
+    ) -> Option<(usize, Option<CardinalDirection>)> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.conditions.iter().all(|c| view.condition(c, rng)) {
+                continue;
+            }
+            let first = match rule.commands.first() {
+                Some(&first) => first,
+                None => return None,
+            };
+            match first.direction() {
+                Some(direction) => {
+                    if let Some(direction) = view.direction(direction, rng) {
+                        return Some((index, Some(direction)));
+                    }
+                }
+                None => return Some((index, None)),
+            }
+        }
+        None
+    }
+
+    fn execute<M, R>(
+        &self,
+        rule: usize,
+        heading: Option<CardinalDirection>,
+        view: &ScriptView,
+        rng: &mut R,
+        messages: &mut ScriptMessages<M>,
+    ) where
+        M: PushMessages,
+        R: Rng,
+    {
+        for (i, &command) in self.rules[rule].commands.iter().enumerate() {
+            let direction = if i == 0 { heading } else { None };
+            messages.command(command, direction, view, rng);
+        }
+    }
+
+    // cards choose and act straight away
+    pub fn run<M, R>(&self, view: &ScriptView, rng: &mut R, messages: &mut ScriptMessages<M>)
+    where
+        M: PushMessages,
+        R: Rng,
+    {
+        if let Some((rule, heading)) = self.choose(view, rng) {
+            self.execute(rule, heading, view, rng, messages);
+        }
+    }
+
+    // npcs choose a turn ahead so the choice can be shown as an intent
+    pub fn plan<R: Rng>(
+        &self,
+        script: ScriptId,
+        view: &ScriptView,
+        rng: &mut R,
+    ) -> Option<(ScriptPlan, Intent)> {
+        self.choose(view, rng).and_then(|(rule, heading)| {
+            self.rules[rule].commands[0]
+                .intent(view.coord(), heading, view.spatial_hash)
+                .map(|intent| {
+                    let plan = ScriptPlan {
+                        script,
+                        rule,
+                        heading,
+                    };
+                    (plan, intent)
+                })
+        })
+    }
+}
+
+impl ScriptPlan {
+    pub fn execute<M, R>(
+        self,
+        scripts: &ScriptLibrary,
+        view: &ScriptView,
+        rng: &mut R,
+        messages: &mut ScriptMessages<M>,
+    ) where
+        M: PushMessages,
+        R: Rng,
+    {
+        scripts
+            .get(self.script)
+            .execute(self.rule, self.heading, view, rng, messages);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NamedScript {
+    name: String,
+    script: Script,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptLibrary {
+    scripts: Vec<NamedScript>,
+}
+
+impl ScriptLibrary {
+    pub fn builtin() -> Self {
+        let scripts = BUILTIN
+            .iter()
+            .map(|&(name, source)| NamedScript {
+                name: name.to_string(),
+                script: Script::parse(source).expect("Invalid builtin script"),
+            })
+            .collect();
+        Self { scripts }
+    }
+
+    pub fn add(&mut self, name: String, script: Script) -> ScriptId {
+        self.scripts.push(NamedScript { name, script });
+        ScriptId(self.scripts.len() - 1)
+    }
+
+    pub fn find(&self, name: &str) -> Option<ScriptId> {
+        self.scripts
+            .iter()
+            .position(|named| named.name == name)
+            .map(ScriptId)
+    }
+
+    pub fn get(&self, ScriptId(index): ScriptId) -> &Script {
+        &self.scripts[index].script
+    }
+
+    pub fn name(&self, ScriptId(index): ScriptId) -> &str {
+        &self.scripts[index].name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, StdRng};
+    use content::Content;
+    use message_queues::MessageQueues;
+    use terrain::TerrainType;
+    use world::World;
+    use super::*;
+
+    // the player faces two target dummies, each with 2 hit points
+    fn world() -> World {
+        let rows = vec!["######", "#@00.#", "######"]
+            .into_iter()
+            .map(|row| row.to_string())
+            .collect();
+        World::new(
+            &TerrainType::Text(rows),
+            &Content::builtin(),
+            &mut MessageQueues::new(),
+        )
+    }
+
+    fn npc(world: &World, x: i32) -> EntityId {
+        npc_at(&world.spatial_hash, Coord::new(x, 1)).expect("No npc")
+    }
+
+    // runs a script as the first dummy, facing the second, and commits what it changes
+    fn run(world: &mut World, source: &str) -> MessageQueues {
+        let script = Script::parse(source).unwrap();
+        let mut queues = MessageQueues::new();
+        {
+            let view = ScriptView {
+                id: npc(world, 2),
+                direction: Some(CardinalDirection::East),
+                entity_store: &world.entity_store,
+                spatial_hash: &world.spatial_hash,
+            };
+            let mut messages = ScriptMessages {
+                id_allocator: &mut world.id_allocator,
+                messages: &mut queues,
+            };
+            script.run(&view, &mut StdRng::from_seed(&[0]), &mut messages);
+        }
+        for change in queues.changes.clone() {
+            world.commit(change);
+        }
+        queues
+    }
+
+    #[test]
+    fn damage_never_takes_hit_points_below_zero() {
+        let mut world = world();
+        let target = npc(&world, 3);
+        run(&mut world, "damage forward 5");
+        assert_eq!(world.entity_store.hit_points.get(&target), Some(&0));
+    }
+
+    #[test]
+    fn healing_stops_at_the_starting_hit_points() {
+        let mut world = world();
+        let id = npc(&world, 2);
+        world.commit(insert::hit_points(id, 1));
+        run(&mut world, "heal 5");
+        assert_eq!(world.entity_store.hit_points.get(&id), Some(&2));
+
+        let queues = run(&mut world, "heal 1");
+        assert!(queues.changes.is_empty());
+    }
+
+    #[test]
+    fn push_moves_the_npc_ahead() {
+        let mut world = world();
+        let target = npc(&world, 3);
+        run(&mut world, "push forward");
+        assert_eq!(world.entity_store.coord.get(&target), Some(&Coord::new(4, 1)));
+    }
+
+    #[test]
+    fn commands_in_a_rule_run_in_order() {
+        let mut world = world();
+        let target = npc(&world, 3);
+        run(&mut world, "damage forward 1; push forward");
+        assert_eq!(world.entity_store.hit_points.get(&target), Some(&1));
+        assert_eq!(world.entity_store.coord.get(&target), Some(&Coord::new(4, 1)));
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let mut world = world();
+        let id = npc(&world, 2);
+        let queues = run(&mut world, "when npc_ahead: wait\nmove back");
+        assert!(queues.changes.is_empty());
+        assert!(queues.animations.is_empty());
+        assert_eq!(world.entity_store.coord.get(&id), Some(&Coord::new(2, 1)));
+    }
+
+    #[test]
+    fn unmet_conditions_fall_through_to_the_next_rule() {
+        let mut world = world();
+        let target = npc(&world, 3);
+        run(&mut world, "when hit_points < 2: push forward\ndamage forward 1");
+        assert_eq!(world.entity_store.hit_points.get(&target), Some(&1));
+        assert_eq!(world.entity_store.coord.get(&target), Some(&Coord::new(3, 1)));
+    }
+}
@@ -0,0 +1,283 @@
+use std::str::FromStr;
+use direction::CardinalDirection;
+use super::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnknownCommand(String),
+    UnknownCondition(String),
+    UnknownDirection(String),
+    ExpectedNumber(String),
+    ExpectedComparison(String),
+    MissingDirection,
+    MissingColon,
+    UnexpectedToken(String),
+    NoRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+type ParseResult<T> = Result<T, ParseErrorKind>;
+
+fn number<T: FromStr>(token: Option<&str>) -> ParseResult<T> {
+    let token = token.unwrap_or("");
+    token
+        .parse()
+        .map_err(|_| ParseErrorKind::ExpectedNumber(token.to_string()))
+}
+
+fn direction(token: Option<&str>) -> ParseResult<Direction> {
+    let token = token.ok_or(ParseErrorKind::MissingDirection)?;
+    let direction = match token {
+        "north" => Direction::Absolute(CardinalDirection::North),
+        "east" => Direction::Absolute(CardinalDirection::East),
+        "south" => Direction::Absolute(CardinalDirection::South),
+        "west" => Direction::Absolute(CardinalDirection::West),
+        "forward" => Direction::Forward,
+        "back" => Direction::Back,
+        "toward_player" => Direction::TowardPlayer,
+        "away_from_player" => Direction::AwayFromPlayer,
+        "random" => Direction::Random,
+        other => return Err(ParseErrorKind::UnknownDirection(other.to_string())),
+    };
+    Ok(direction)
+}
+
+fn comparison<'a, I: Iterator<Item = &'a str>>(tokens: &mut I) -> ParseResult<Comparison> {
+    match tokens.next() {
+        Some("<") => Ok(Comparison::Below(number(tokens.next())?)),
+        Some(">") => Ok(Comparison::Above(number(tokens.next())?)),
+        other => Err(ParseErrorKind::ExpectedComparison(
+            other.unwrap_or("").to_string(),
+        )),
+    }
+}
+
+fn condition<'a, I: Iterator<Item = &'a str>>(tokens: &mut I) -> ParseResult<Condition> {
+    let condition = match tokens.next().unwrap_or("") {
+        "not" => Condition::Not(Box::new(condition(tokens)?)),
+        "player_in_line" => Condition::PlayerInLine,
+        "player_adjacent" => Condition::PlayerAdjacent,
+        "player_visible" => Condition::PlayerVisible,
+        "npc_ahead" => Condition::NpcAhead,
+        "chance" => Condition::Chance(number(tokens.next())?),
+        "hit_points" => Condition::HitPoints(comparison(tokens)?),
+        "distance" => Condition::Distance(comparison(tokens)?),
+        other => return Err(ParseErrorKind::UnknownCondition(other.to_string())),
+    };
+    Ok(condition)
+}
+
+fn conditions(text: &str) -> ParseResult<Vec<Condition>> {
+    let mut conditions = Vec::new();
+    let mut tokens = text.split_whitespace();
+    loop {
+        conditions.push(condition(&mut tokens)?);
+        match tokens.next() {
+            Some("and") => (),
+            Some(other) => return Err(ParseErrorKind::UnexpectedToken(other.to_string())),
+            None => break,
+        }
+    }
+    Ok(conditions)
+}
+
+fn command(text: &str) -> ParseResult<Option<Command>> {
+    let mut tokens = text.split_whitespace();
+    let command = match tokens.next().unwrap_or("") {
+        "move" => Command::Move(direction(tokens.next())?),
+        "punch" => Command::Punch(direction(tokens.next())?),
+        "shoot" => Command::Shoot(direction(tokens.next())?),
+        "charge" => Command::Charge(direction(tokens.next())?),
+        "push" => Command::Push(direction(tokens.next())?),
+        "damage" => {
+            let direction = direction(tokens.next())?;
+            Command::Damage(direction, number(tokens.next())?)
+        }
+        "heal" => Command::Heal(number(tokens.next())?),
+        "summon" => Command::Summon,
+        "wait" => return expect_end(tokens).map(|_| None),
+        other => return Err(ParseErrorKind::UnknownCommand(other.to_string())),
+    };
+    expect_end(tokens).map(|_| Some(command))
+}
+
+fn expect_end<'a, I: Iterator<Item = &'a str>>(mut tokens: I) -> ParseResult<()> {
+    match tokens.next() {
+        Some(other) => Err(ParseErrorKind::UnexpectedToken(other.to_string())),
+        None => Ok(()),
+    }
+}
+
+// "wait" contributes no commands, so a rule of only waits does nothing
+fn commands(text: &str) -> ParseResult<Vec<Command>> {
+    let mut commands = Vec::new();
+    for text in text.split(';') {
+        if let Some(command) = command(text)? {
+            commands.push(command);
+        }
+    }
+    Ok(commands)
+}
+
+fn rule(line: &str) -> ParseResult<Rule> {
+    if line.starts_with("when ") {
+        let colon = line.find(':').ok_or(ParseErrorKind::MissingColon)?;
+        Ok(Rule {
+            conditions: conditions(&line["when ".len()..colon])?,
+            commands: commands(&line[colon + 1..])?,
+        })
+    } else {
+        Ok(Rule {
+            conditions: Vec::new(),
+            commands: commands(line)?,
+        })
+    }
+}
+
+pub fn parse(source: &str) -> Result<Script, ParseError> {
+    let mut script = Script {
+        noise: DEFAULT_NOISE,
+        rules: Vec::new(),
+    };
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let result = if line.starts_with("noise ") {
+            number(line.split_whitespace().nth(1)).map(|noise| script.noise = noise)
+        } else {
+            rule(line).map(|rule| script.rules.push(rule))
+        };
+        result.map_err(|kind| ParseError {
+            line: index + 1,
+            kind,
+        })?;
+    }
+
+    if script.rules.is_empty() {
+        return Err(ParseError {
+            line: 0,
+            kind: ParseErrorKind::NoRules,
+        });
+    }
+
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> ParseError {
+        parse(source).expect_err("Script should not parse")
+    }
+
+    #[test]
+    fn conditions_are_joined_with_and() {
+        let script = parse("when player_in_line and not hit_points < 2: shoot toward_player")
+            .unwrap();
+        assert_eq!(script.rules.len(), 1);
+        let rule = &script.rules[0];
+        assert_eq!(rule.conditions.len(), 2);
+        match rule.conditions[0] {
+            Condition::PlayerInLine => (),
+            ref other => panic!("Unexpected condition {:?}", other),
+        }
+        match rule.conditions[1] {
+            Condition::Not(ref condition) => match **condition {
+                Condition::HitPoints(Comparison::Below(2)) => (),
+                ref other => panic!("Unexpected condition {:?}", other),
+            },
+            ref other => panic!("Unexpected condition {:?}", other),
+        }
+        assert_eq!(rule.commands.len(), 1);
+        match rule.commands[0] {
+            Command::Shoot(Direction::TowardPlayer) => (),
+            other => panic!("Unexpected command {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_rule_runs_every_command_in_order() {
+        let script = parse("damage forward 2; push forward; heal 1").unwrap();
+        let commands = &script.rules[0].commands;
+        assert_eq!(commands.len(), 3);
+        match (commands[0], commands[1], commands[2]) {
+            (Command::Damage(Direction::Forward, 2), Command::Push(Direction::Forward), Command::Heal(1)) => (),
+            other => panic!("Unexpected commands {:?}", other),
+        }
+    }
+
+    #[test]
+    fn noise_defaults_and_can_be_set() {
+        assert_eq!(parse("summon").unwrap().noise, DEFAULT_NOISE);
+        assert_eq!(parse("noise 7\nsummon").unwrap().noise, 7);
+        assert_eq!(
+            error("noise loud\nsummon"),
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::ExpectedNumber("loud".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn wait_adds_no_commands() {
+        let script = parse("when chance 50: wait\nmove random").unwrap();
+        assert!(script.rules[0].commands.is_empty());
+        assert_eq!(script.rules.len(), 2);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let script = parse("# a comment\n\nmove forward # trailing\n").unwrap();
+        assert_eq!(script.rules.len(), 1);
+    }
+
+    #[test]
+    fn malformed_rules_report_their_line() {
+        let cases = vec![
+            ("move forward\njump", 2, ParseErrorKind::UnknownCommand("jump".to_string())),
+            ("when player_in_line shoot forward", 1, ParseErrorKind::MissingColon),
+            ("when lucky: move forward", 1, ParseErrorKind::UnknownCondition("lucky".to_string())),
+            ("move sideways", 1, ParseErrorKind::UnknownDirection("sideways".to_string())),
+            ("punch", 1, ParseErrorKind::MissingDirection),
+            ("when distance = 2: wait", 1, ParseErrorKind::ExpectedComparison("=".to_string())),
+            ("heal lots", 1, ParseErrorKind::ExpectedNumber("lots".to_string())),
+            ("summon now", 1, ParseErrorKind::UnexpectedToken("now".to_string())),
+            (
+                "when npc_ahead or player_adjacent: wait",
+                1,
+                ParseErrorKind::UnexpectedToken("or".to_string()),
+            ),
+        ];
+        for (source, line, kind) in cases {
+            assert_eq!(error(source), ParseError { line, kind }, "{}", source);
+        }
+    }
+
+    #[test]
+    fn a_script_needs_a_rule() {
+        assert_eq!(
+            error("# nothing\nnoise 2"),
+            ParseError {
+                line: 0,
+                kind: ParseErrorKind::NoRules,
+            }
+        );
+    }
+
+    #[test]
+    fn builtin_scripts_parse() {
+        for &(name, source) in BUILTIN.iter() {
+            assert!(parse(source).is_ok(), "{}", name);
+        }
+    }
+}
@@ -1,21 +1,34 @@
+use std::mem;
 use std::time::Duration;
 use std::collections::HashSet;
 use grid_2d::Size;
+use direction::CardinalDirection;
 use entity_store::*;
 use input::Input;
 use policy;
 use card::*;
+use intent::Intent;
+use behaviour::Behaviour;
+use awareness::{Awareness, Noise};
+use pathfinding;
 use card_state::*;
 use animation::*;
-use rand::{SeedableRng, StdRng};
+use rand::{Rng, SeedableRng, StdRng};
 use pathfinding::PathfindingContext;
 use message_queues::*;
-use terrain::TerrainType;
-use world::World;
+use world::{World, WorldSaveState};
+use dungeon::{Dungeon, DungeonSaveState, LevelChange};
 use change::ChangeContext;
+use levels::LevelSet;
+use tutorial::Tutorial;
+use script::{ScriptLibrary, ScriptMessages, ScriptView};
+use content::{Content, PackId};
 use event::*;
 
-const INITIAL_HAND_SIZE: usize = 4;
+pub const INITIAL_HAND_SIZE: usize = 4;
+
+// long enough that every slide advances a cell per tick, short enough not to skip a punch
+const HEADLESS_PERIOD_MILLIS: u64 = 100;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum InputState {
@@ -32,6 +45,7 @@ enum TurnState {
 #[derive(Clone, Debug)]
 pub struct State {
     world: World,
+    dungeon: Dungeon,
     card_state: CardState,
     messages: MessageQueues,
     swap_messages: MessageQueuesSwap,
@@ -43,103 +57,147 @@ pub struct State {
     turn: TurnState,
     pathfinding: PathfindingContext,
     change_context: ChangeContext,
+    tutorial: Option<Tutorial>,
+    gameplay_events: Vec<GameplayEvent>,
+    content: Content,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SaveState {
-    changes: Vec<EntityChange>,
-    id_allocator: EntityIdAllocator,
-    count: u64,
+    world: WorldSaveState,
+    dungeon: DungeonSaveState,
     player_id: EntityId,
     card_state: CardState,
     input_state: InputState,
     next_rng_seed: usize,
-    size: Size,
     turn: TurnState,
     messages: MessageQueues,
+    tutorial: Option<Tutorial>,
+    content: Content,
+}
+
+// just enough of a game to draw it, so spectators aren't sent the whole dungeon
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ViewState {
+    world: WorldSaveState,
+    level_set: LevelSet,
+    depth: usize,
+    player_id: EntityId,
+    card_state: CardState,
+    input_state: InputState,
+    tutorial: Option<Tutorial>,
+    packs: Vec<PackId>,
+}
+
+impl ViewState {
+    pub fn packs(&self) -> &[PackId] {
+        &self.packs
+    }
+}
+
+// the player arrives on the stairs they came by, or on the nearest free cell if an npc is
+// standing there
+fn arrival_coord(world: &World, change: LevelChange) -> Option<Coord> {
+    let entity_store = &world.entity_store;
+    let stairs_id = match change {
+        LevelChange::Down => entity_store.up_stairs.iter().next(),
+        LevelChange::Up => entity_store.stairs.iter().next(),
+    };
+    let stairs_coord = match stairs_id.and_then(|id| entity_store.coord.get(id)) {
+        Some(&coord) => coord,
+        None => return None,
+    };
+    let spatial_hash = &world.spatial_hash;
+    let free = |coord: Coord| {
+        spatial_hash.get(coord).map_or(false, |cell| {
+            cell.solid_count == 0 && cell.npc_set.is_empty() && cell.player_count == 0
+                && (coord == stairs_coord
+                    || (cell.stairs_count == 0 && cell.up_stairs_count == 0))
+        })
+    };
+    if free(stairs_coord) {
+        return Some(stairs_coord);
+    }
+    let size = world.size();
+    let mut pathfinding = PathfindingContext::new(size);
+    pathfinding.update_player_map(stairs_coord, spatial_hash);
+    let mut closest = None;
+    for y in 0..size.height() as i32 {
+        for x in 0..size.width() as i32 {
+            let coord = Coord::new(x, y);
+            if let Some(distance) = pathfinding.distance_to_player(coord) {
+                if free(coord) && closest.map_or(true, |(best, _)| distance < best) {
+                    closest = Some((distance, coord));
+                }
+            }
+        }
+    }
+    closest.map(|(_, coord)| coord)
 }
 
 impl State {
 
-    pub fn switch_levels(&mut self) {
-        let terrain = TerrainType::StaticStrings(vec![
-            "##########",
-            "#.....1..#",
-            "#..m.....#",
-            "#m..1....#",
-            "#1.......#",
-            "#..#####.#",
-            "#........#",
-            "#.....@>.#",
-            "#........#",
-            "##########",
-        ]);
-
-        let mut next_world = World::new(&terrain, &mut self.messages);
-
-        let next_player_id = *next_world.entity_store.player.iter().next().expect("No player");
-
-        for change in self.world.component_drain_insert(self.player_id, next_player_id) {
-            if change.typ() == ComponentType::Coord {
-                // otherwise the player would be moved to their old position in the new level
-                continue;
+    fn change_level(&mut self, change: LevelChange, next_depth: usize) {
+        let previous_depth = self.dungeon.depth();
+        let mut next_world = self.dungeon.level(next_depth, &self.content, &mut self.messages);
+
+        let arrival_coord = match arrival_coord(&next_world, change) {
+            Some(arrival_coord) => arrival_coord,
+            None => {
+                // there's nowhere to stand on the next level, so the player stays on this one
+                self.dungeon.store(next_depth, next_world);
+                return;
             }
+        };
+        self.dungeon.enter(next_depth);
+
+        // the player leaves the spatial hash of the level being stored
+        self.world
+            .commit(EntityChange::Remove(self.player_id, ComponentType::Coord));
+
+        let next_player_id = next_world.id_allocator.allocate();
 
-            next_world.commit(change);
+        for component in self.world.component_drain_insert(self.player_id, next_player_id) {
+            next_world.commit(component);
         }
 
-        let player_coord = *next_world.entity_store.coord.get(&next_player_id).expect("No player coord");
-        self.messages.player_moved_to = Some(player_coord);
+        self.world.id_allocator.free(self.player_id);
+
+        next_world.commit(insert::coord(next_player_id, arrival_coord));
+
+        self.messages.player_moved_to = Some(arrival_coord);
+        self.messages.noise = None;
+
+        if next_world.size() != self.world.size() {
+            self.pathfinding = PathfindingContext::new(next_world.size());
+        }
+
+        let previous_world = mem::replace(&mut self.world, next_world);
+        self.dungeon.store(previous_depth, previous_world);
 
         self.player_id = next_player_id;
-        self.world = next_world;
         self.turn = TurnState::Player;
     }
 
-    pub fn new(rng_seed: usize) -> Self {
-        let mut rng = StdRng::from_seed(&[rng_seed]);
-
-        let terrain = TerrainType::StaticStrings(vec![
-            "##########",
-            "#@.>.....#",
-            "##..1.#..#",
-            "#.....#..#",
-            "#.....#..#",
-            "###.###..#",
-            "#........#",
-            "#........#",
-            "#........#",
-            "##########",
-        ]);
-
+    fn build(
+        mut rng: StdRng,
+        level_set: LevelSet,
+        card_state: CardState,
+        tutorial: Option<Tutorial>,
+        content: Content,
+    ) -> Self {
         let mut messages = MessageQueues::new();
 
-        let world = World::new(&terrain, &mut messages);
+        let mut dungeon = Dungeon::new(level_set, rng.gen());
+
+        let world = dungeon.level(0, &content, &mut messages);
 
         let player_id = *world.entity_store.player.iter().next().expect("No player");
 
         let player_coord = *world.entity_store.coord.get(&player_id).expect("No player coord");
         messages.player_moved_to = Some(player_coord);
 
-        let card_state = CardState::new(
-            vec![
-                Card::Punch,
-                Card::Punch,
-                Card::Move,
-                Card::Move,
-                Card::Shoot,
-                Card::Shoot,
-                Card::Shoot,
-                Card::Shoot,
-                Card::Shoot,
-                Card::Shoot,
-                Card::Shoot,
-            ],
-            INITIAL_HAND_SIZE,
-            &mut rng,
-        );
-
-        Self {
+        let mut state = Self {
             input_state: InputState::WaitingForCardSelection,
             player_id,
             card_state,
@@ -152,26 +210,162 @@ impl State {
             seen_animation_channels: HashSet::new(),
             change_context: ChangeContext::new(),
             world,
-        }
+            dungeon,
+            tutorial,
+            gameplay_events: Vec::new(),
+            content,
+        };
+
+        state.update_tutorial();
+
+        state
+    }
+
+    pub fn new(rng_seed: usize) -> Self {
+        Self::with_content(rng_seed, Content::builtin())
+    }
+
+    pub fn with_content(rng_seed: usize, content: Content) -> Self {
+        let mut cards = standard_deck();
+        cards.extend(content.extra_cards().iter().cloned());
+        Self::with_deck(rng_seed, cards, INITIAL_HAND_SIZE, content)
+    }
+
+    pub fn with_deck(rng_seed: usize, cards: Vec<Card>, hand_size: usize, content: Content) -> Self {
+        let mut rng = StdRng::from_seed(&[rng_seed]);
+        let card_state = CardState::new(cards, hand_size, &mut rng);
+        Self::build(rng, LevelSet::Standard, card_state, None, content)
+    }
+
+    pub fn playtest(rng_seed: usize, rows: Vec<String>, content: Content) -> Self {
+        let mut rng = StdRng::from_seed(&[rng_seed]);
+
+        let cards = vec![
+            Card::Punch,
+            Card::Punch,
+            Card::Move,
+            Card::Move,
+            Card::Move,
+            Card::Move,
+            Card::Shoot,
+            Card::Shoot,
+            Card::Shoot,
+            Card::Shoot,
+        ];
+        let card_state = CardState::new(cards, INITIAL_HAND_SIZE, &mut rng);
+
+        let content = content.with_level(0, rows);
+
+        Self::build(rng, LevelSet::Playtest, card_state, None, content)
+    }
+
+    pub fn tutorial(rng_seed: usize) -> Self {
+        let rng = StdRng::from_seed(&[rng_seed]);
+
+        // cards are drawn from the end, so the opening hand is move, move, punch, shoot
+        let card_state = CardState::unshuffled(
+            vec![
+                Card::Move,
+                Card::Shoot,
+                Card::Move,
+                Card::Move,
+                Card::Shoot,
+                Card::Move,
+                Card::Move,
+                Card::Shoot,
+                Card::Move,
+                Card::Move,
+                Card::Punch,
+                Card::Move,
+                Card::Move,
+                Card::Move,
+                Card::Shoot,
+                Card::Punch,
+                Card::Move,
+                Card::Move,
+            ],
+            INITIAL_HAND_SIZE,
+        );
+
+        Self::build(
+            rng,
+            LevelSet::Tutorial,
+            card_state,
+            Some(Tutorial::new()),
+            Content::builtin(),
+        )
     }
 
     pub fn save(&self, next_rng_seed: usize) -> SaveState {
-        let mut changes = Vec::with_capacity(1024);
-        self.world.entity_store.clone_changes(&mut changes);
         SaveState {
-            changes,
-            id_allocator: self.world.id_allocator.clone(),
-            count: self.world.count,
+            world: self.world.save(),
+            dungeon: self.dungeon.save(),
             player_id: self.player_id,
             card_state: self.card_state.clone(),
             input_state: self.input_state.clone(),
             next_rng_seed,
-            size: self.world.size(),
             turn: self.turn,
             messages: self.messages.clone(),
+            tutorial: self.tutorial.clone(),
+            content: self.content.clone(),
         }
     }
 
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            world: self.world.save(),
+            level_set: self.dungeon.level_set(),
+            depth: self.dungeon.depth(),
+            player_id: self.player_id,
+            card_state: self.card_state.clone(),
+            input_state: self.input_state.clone(),
+            tutorial: self.tutorial.clone(),
+            packs: self.content.packs().to_vec(),
+        }
+    }
+
+    // the viewer's content stands in for the game's, so it needs the same packs loaded
+    pub fn from_view_state(view_state: ViewState, content: Content) -> Self {
+        let ViewState {
+            world,
+            level_set,
+            depth,
+            player_id,
+            card_state,
+            input_state,
+            tutorial,
+            packs: _,
+        } = view_state;
+        let world = World::from(world);
+        Self {
+            pathfinding: PathfindingContext::new(world.size()),
+            world,
+            dungeon: Dungeon::at_depth(level_set, depth),
+            input_state,
+            player_id,
+            card_state,
+            rng: StdRng::from_seed(&[0]),
+            turn: TurnState::Player,
+            messages: MessageQueues::new(),
+            swap_messages: MessageQueuesSwap::new(),
+            npc_order: Vec::new(),
+            seen_animation_channels: HashSet::new(),
+            change_context: ChangeContext::new(),
+            tutorial,
+            gameplay_events: Vec::new(),
+            content,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.dungeon.depth()
+    }
+    pub fn num_floors(&self) -> usize {
+        self.dungeon.num_floors()
+    }
+    pub fn size(&self) -> Size {
+        self.world.size()
+    }
     pub fn entity_store(&self) -> &EntityStore {
         &self.world.entity_store
     }
@@ -184,6 +378,42 @@ impl State {
     pub fn input_state(&self) -> &InputState {
         &self.input_state
     }
+    pub fn scripts(&self) -> &ScriptLibrary {
+        self.content.scripts()
+    }
+    pub fn content(&self) -> &Content {
+        &self.content
+    }
+    pub fn tutorial_prompt(&self) -> Option<&'static [&'static str]> {
+        self.tutorial.as_ref().and_then(Tutorial::prompt)
+    }
+    pub fn gameplay_events(&self) -> &[GameplayEvent] {
+        &self.gameplay_events
+    }
+    pub fn turn_count(&self) -> u64 {
+        self.dungeon.turns()
+    }
+    pub fn player_coord(&self) -> Option<Coord> {
+        self.world.entity_store.coord.get(&self.player_id).cloned()
+    }
+    pub fn is_awaiting_input(&self) -> bool {
+        self.messages.animations.is_empty() && match self.turn {
+            TurnState::Player => true,
+            TurnState::Npcs => false,
+        }
+    }
+    pub fn for_each_intent_cell<F>(&self, mut f: F)
+    where
+        F: FnMut(Coord, Intent),
+    {
+        let entity_store = &self.world.entity_store;
+        for (id, intent) in entity_store.intent.iter() {
+            if let Some(coord) = entity_store.coord.get(&id) {
+                let intent = *intent;
+                intent.for_each_cell(*coord, &self.world.spatial_hash, |cell| f(cell, intent));
+            }
+        }
+    }
 
     fn player_turn(&mut self, input: Input) -> Option<Event> {
         match input {
@@ -198,8 +428,11 @@ impl State {
                     card.play(
                         self.player_id,
                         &self.world.entity_store,
+                        &self.world.spatial_hash,
                         direction,
                         &mut self.world.id_allocator,
+                        self.content.scripts(),
+                        &mut self.rng,
                         &mut self.messages,
                     );
                     if policy::precheck(
@@ -207,11 +440,24 @@ impl State {
                         &self.world.entity_store,
                         &self.world.spatial_hash,
                     ) {
+                        let player_coord = *self.world
+                            .entity_store
+                            .coord
+                            .get(&self.player_id)
+                            .expect("No player coord");
+                        self.messages.noise = Some(Noise {
+                            origin: player_coord,
+                            volume: card.noise(self.content.scripts()),
+                        });
+
                         let card_to_check = self.card_state.hand.remove_card(index);
                         assert_eq!(card, card_to_check);
                         self.card_state.fill_hand();
                         self.input_state = InputState::WaitingForCardSelection;
                         self.turn = TurnState::Npcs;
+                        if let Some(ref mut tutorial) = self.tutorial {
+                            tutorial.card_played();
+                        }
 
                         let ret = self.change_context.process(
                             &mut self.world,
@@ -222,6 +468,7 @@ impl State {
                         );
 
                         self.world.count += 1;
+                        self.dungeon.count_turn();
 
                         ret
                     } else {
@@ -239,6 +486,31 @@ impl State {
         }
     }
 
+    // whether playing the card would be accepted, checked the same way as a real play
+    pub fn can_play(&self, index: HandIndex, direction: CardinalDirection) -> bool {
+        let card = if let Some(card) = self.card_state.hand.get(index) {
+            *card
+        } else {
+            return false;
+        };
+        let mut messages = MessageQueues::new();
+        card.play(
+            self.player_id,
+            &self.world.entity_store,
+            &self.world.spatial_hash,
+            direction,
+            &mut self.world.id_allocator.clone(),
+            self.content.scripts(),
+            &mut self.rng.clone(),
+            &mut messages,
+        );
+        policy::precheck(
+            &messages.changes,
+            &self.world.entity_store,
+            &self.world.spatial_hash,
+        )
+    }
+
     fn all_npc_turns(&mut self) -> Option<Event> {
         self.turn = TurnState::Player;
 
@@ -255,13 +527,108 @@ impl State {
         self.pathfinding
             .sort_entities_by_distance_to_player(&self.world.entity_store, &mut self.npc_order);
 
+        let player_coord = *self.world
+            .entity_store
+            .coord
+            .get(&self.player_id)
+            .expect("No player coord");
+
+        if let Some(noise) = self.messages.noise.take() {
+            for &id in self.npc_order.iter() {
+                let entity_store = &self.world.entity_store;
+                if let Some(&awareness) = entity_store.awareness.get(&id) {
+                    let coord = *entity_store.coord.get(&id).expect("Entity missing coord");
+                    let next = awareness.hear(noise, coord);
+                    if next != awareness {
+                        self.messages.change(insert::awareness(id, next));
+                    }
+                }
+            }
+            if let Some(meta) = self.change_context.process(
+                &mut self.world,
+                &mut self.card_state,
+                &mut self.messages,
+                &mut self.swap_messages,
+                &mut self.rng,
+            ) {
+                return Some(meta);
+            }
+        }
+
         for &id in self.npc_order.iter() {
-            self.pathfinding.act(
+            let coord = if let Some(coord) = self.world.entity_store.coord.get(&id) {
+                *coord
+            } else {
+                continue;
+            };
+
+            // planning happens once per turn, below, so an intent that no longer makes sense
+            // is dropped rather than replaced
+            let intent = match self.world.entity_store.intent.get(&id).cloned() {
+                Some(intent) if intent.is_valid(coord, &self.world.spatial_hash) => Some(intent),
+                _ => None,
+            };
+
+            if let Some(intent) = intent {
+                // scripted npcs carry out the rule they planned, of which the intent is a preview
+                if let Some(&script_plan) = self.world.entity_store.script_plan.get(&id) {
+                    let view = ScriptView {
+                        id,
+                        direction: None,
+                        entity_store: &self.world.entity_store,
+                        spatial_hash: &self.world.spatial_hash,
+                    };
+                    let mut messages = ScriptMessages {
+                        id_allocator: &mut self.world.id_allocator,
+                        messages: &mut self.messages,
+                    };
+                    script_plan.execute(
+                        self.content.scripts(),
+                        &view,
+                        &mut self.rng,
+                        &mut messages,
+                    );
+                } else {
+                    intent.execute(
+                        id,
+                        &self.world.entity_store,
+                        &self.world.spatial_hash,
+                        &mut self.world.id_allocator,
+                        &mut self.messages,
+                    );
+                }
+            }
+
+            if let Some(meta) = self.change_context.process(
+                &mut self.world,
+                &mut self.card_state,
+                &mut self.messages,
+                &mut self.swap_messages,
+                &mut self.rng,
+            ) {
+                return Some(meta);
+            }
+
+            // decide what to do next turn so the plan can be shown to the player
+            let next_intent = plan(
                 id,
-                &self.world.entity_store,
-                &self.world.spatial_hash,
+                player_coord,
+                &self.world,
+                &mut self.pathfinding,
+                self.content.scripts(),
+                &mut self.rng,
                 &mut self.messages,
             );
+            match next_intent {
+                Some(intent) => self.messages.change(insert::intent(id, intent)),
+                None => {
+                    if self.world.entity_store.intent.get(&id).is_some() {
+                        self.messages
+                            .change(EntityChange::Remove(id, ComponentType::Intent));
+                    }
+                }
+            }
+
             if let Some(meta) = self.change_context.process(
                 &mut self.world,
                 &mut self.card_state,
@@ -303,14 +670,39 @@ impl State {
         )
     }
 
+    fn update_tutorial(&mut self) {
+        if let Some(ref mut tutorial) = self.tutorial {
+            if let Some(player_coord) = self.world.entity_store.coord.get(&self.player_id) {
+                tutorial.update(
+                    *player_coord,
+                    self.dungeon.depth(),
+                    &self.gameplay_events,
+                    &self.card_state,
+                );
+            }
+        }
+    }
+
     pub fn tick<I>(&mut self, inputs: I, period: Duration) -> Option<ExternalEvent>
     where
         I: IntoIterator<Item = Input>,
     {
+        self.gameplay_events.clear();
+
         let event = if self.messages.animations.is_empty() {
             match self.turn {
                 TurnState::Player => {
-                    if let Some(input) = inputs.into_iter().next() {
+                    let next_input = {
+                        let tutorial = &mut self.tutorial;
+                        let card_state = &self.card_state;
+                        inputs.into_iter().find(|input| {
+                            tutorial
+                                .as_mut()
+                                .map(|tutorial| tutorial.accept(input, card_state))
+                                .unwrap_or(true)
+                        })
+                    };
+                    if let Some(input) = next_input {
                         self.player_turn(input)
                     } else {
                         None
@@ -322,50 +714,189 @@ impl State {
             self.animation_tick(period)
         };
 
-        match event {
+        self.gameplay_events
+            .extend(self.messages.gameplay_events.drain(..));
+
+        let external_event = match event {
             Some(Event::External(external_event)) => Some(external_event),
-            Some(Event::NextLevel) => {
-                self.switch_levels();
+            Some(Event::ChangeLevel(change)) => match self.dungeon.next_depth(change) {
+                Some(next_depth) => {
+                    self.change_level(change, next_depth);
+                    None
+                }
+                // leaving the last floor by its stairs completes the run
+                None => match change {
+                    LevelChange::Down => Some(ExternalEvent::Victory),
+                    LevelChange::Up => None,
+                },
+            },
+            None => None,
+        };
+
+        self.update_tutorial();
+
+        let tutorial_complete = self.tutorial
+            .as_ref()
+            .map(Tutorial::is_complete)
+            .unwrap_or(false);
+        if external_event.is_none() && tutorial_complete {
+            return Some(ExternalEvent::TutorialComplete);
+        }
+
+        external_event
+    }
+
+    // applies the inputs one at a time, running npc turns and animations to completion
+    pub fn headless_turn<I>(&mut self, inputs: I) -> Option<ExternalEvent>
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        let period = Duration::from_millis(HEADLESS_PERIOD_MILLIS);
+        let mut inputs = inputs.into_iter();
+        let mut gameplay_events = Vec::new();
+        let mut event = None;
+        loop {
+            let input = if self.is_awaiting_input() {
+                match inputs.next() {
+                    Some(input) => Some(input),
+                    None => break,
+                }
+            } else {
                 None
+            };
+            let tick_event = self.tick(input, period);
+            gameplay_events.extend(self.gameplay_events.drain(..));
+            match tick_event {
+                Some(ExternalEvent::BossPhaseChanged(phase)) => {
+                    event = Some(ExternalEvent::BossPhaseChanged(phase));
+                }
+                Some(other) => {
+                    event = Some(other);
+                    break;
+                }
+                None => (),
             }
-            None => None,
         }
+        // events from every tick of the turn stay visible until the next tick
+        self.gameplay_events = gameplay_events;
+        event
+    }
+}
+
+pub fn standard_deck() -> Vec<Card> {
+    vec![
+        Card::Punch,
+        Card::Punch,
+        Card::Move,
+        Card::Move,
+        Card::Shoot,
+        Card::Shoot,
+        Card::Shoot,
+        Card::Shoot,
+        Card::Shoot,
+        Card::Shoot,
+        Card::Shoot,
+    ]
+}
+
+fn plan(
+    id: EntityId,
+    player_coord: Coord,
+    world: &World,
+    pathfinding: &mut PathfindingContext,
+    scripts: &ScriptLibrary,
+    rng: &mut StdRng,
+    messages: &mut MessageQueues,
+) -> Option<Intent> {
+    let behaviour = if let Some(behaviour) = world.entity_store.behaviour.get(&id) {
+        *behaviour
+    } else {
+        return None;
+    };
+
+    if world.entity_store.script_plan.get(&id).is_some() {
+        messages.change(EntityChange::Remove(id, ComponentType::ScriptPlan));
+    }
+
+    let coord = world
+        .entity_store
+        .coord
+        .get(&id)
+        .cloned()
+        .expect("Entity missing coord");
+
+    // npcs without an awareness component always know where the player is
+    let awareness = if let Some(&awareness) = world.entity_store.awareness.get(&id) {
+        let next = awareness.look(coord, player_coord, &world.spatial_hash);
+        if next != awareness {
+            messages.change(insert::awareness(id, next));
+        }
+        next
+    } else {
+        Awareness::Alerted(player_coord)
+    };
+
+    match awareness {
+        Awareness::Alerted(_) => behaviour.plan(
+            id,
+            &world.entity_store,
+            &world.spatial_hash,
+            pathfinding,
+            scripts,
+            rng,
+            messages,
+        ),
+        Awareness::Suspicious(target) => {
+            if let Some(direction) = pathfinding::step_towards(&world.spatial_hash, coord, target) {
+                Some(Intent::towards(coord, direction, &world.spatial_hash))
+            } else {
+                // can't get any closer, so give up searching
+                messages.change(insert::awareness(id, Awareness::Unaware));
+                None
+            }
+        }
+        Awareness::Unaware => match behaviour {
+            Behaviour::Patrol(_) => behaviour.plan(
+                id,
+                &world.entity_store,
+                &world.spatial_hash,
+                pathfinding,
+                scripts,
+                rng,
+                messages,
+            ),
+            _ => None,
+        },
+    }
+}
+
+impl SaveState {
+    pub fn packs(&self) -> &[PackId] {
+        self.content.packs()
     }
 }
 
 impl From<SaveState> for State {
     fn from(
         SaveState {
-            mut changes,
-            id_allocator,
-            count,
+            world,
+            dungeon,
             player_id,
             card_state,
             input_state,
             next_rng_seed,
-            size,
             turn,
             messages,
+            tutorial,
+            content,
         }: SaveState,
     ) -> Self {
-        let mut entity_store = EntityStore::new();
-        let mut spatial_hash = SpatialHashTable::new(size);
-        let mut entity_components = EntityComponentTable::new();
-
-        for change in changes.drain(..) {
-            spatial_hash.update(&entity_store, &change, 0);
-            entity_components.update(&change);
-            entity_store.commit(change);
-        }
+        let world = World::from(world);
 
         Self {
-            world: World {
-                entity_store,
-                spatial_hash,
-                entity_components,
-                id_allocator,
-                count,
-            },
+            pathfinding: PathfindingContext::new(world.size()),
+            world,
+            dungeon: Dungeon::from(dungeon),
             input_state,
             player_id,
             card_state,
@@ -373,10 +904,128 @@ impl From<SaveState> for State {
             turn,
             messages,
             swap_messages: MessageQueuesSwap::new(),
-            pathfinding: PathfindingContext::new(size),
             npc_order: Vec::new(),
             seen_animation_channels: HashSet::new(),
             change_context: ChangeContext::new(),
+            tutorial,
+            gameplay_events: Vec::new(),
+            content,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use terrain::TerrainType;
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    fn state() -> State {
+        let content = Content::builtin()
+            .with_level(0, rows(&["#####", "#@.>#", "#...#", "#####"]))
+            .with_level(
+                1,
+                rows(&[
+                    "############",
+                    "#<.........#",
+                    "#..........#",
+                    "#..........#",
+                    "#..........#",
+                    "#..........#",
+                    "#..........#",
+                    "#.........>#",
+                    "############",
+                ]),
+            );
+        State::with_content(0, content)
+    }
+
+    #[test]
+    fn moving_down_to_a_larger_level_rebuilds_the_spatial_hash_and_pathfinding() {
+        let mut state = state();
+        assert_eq!(state.size(), Size::new(5, 4));
+
+        state.change_level(LevelChange::Down, 1);
+        assert_eq!(state.size(), Size::new(12, 9));
+
+        let arrival = Coord::new(1, 1);
+        let far = Coord::new(10, 7);
+        assert_eq!(state.player_coord(), Some(arrival));
+        assert_eq!(state.spatial_hash().get(arrival).unwrap().player_count, 1);
+        assert!(state.spatial_hash().get(far).is_some());
+
+        // the player map is rebuilt at the start of the npcs' turn
+        state.all_npc_turns();
+        assert_eq!(state.pathfinding.distance_to_player(far), Some(15));
+    }
+
+    #[test]
+    fn moving_up_to_a_smaller_level_rebuilds_the_spatial_hash_and_pathfinding() {
+        let mut state = state();
+        state.change_level(LevelChange::Down, 1);
+        state.all_npc_turns();
+
+        state.change_level(LevelChange::Up, 0);
+        assert_eq!(state.size(), Size::new(5, 4));
+
+        let arrival = Coord::new(3, 1);
+        assert_eq!(state.player_coord(), Some(arrival));
+        assert_eq!(state.spatial_hash().get(arrival).unwrap().player_count, 1);
+        assert!(state.spatial_hash().get(Coord::new(10, 7)).is_none());
+
+        state.all_npc_turns();
+        assert_eq!(state.pathfinding.distance_to_player(Coord::new(1, 2)), Some(3));
+    }
+
+    #[test]
+    fn the_level_left_behind_no_longer_holds_the_player() {
+        let mut state = state();
+        state.change_level(LevelChange::Down, 1);
+        state.change_level(LevelChange::Up, 0);
+
+        let players = state.entity_store().player.iter().count();
+        assert_eq!(players, 1);
+        state.change_level(LevelChange::Down, 1);
+        assert_eq!(state.spatial_hash().get(Coord::new(1, 1)).unwrap().player_count, 1);
+    }
+
+    fn world(level: &[&str]) -> World {
+        World::new(
+            &TerrainType::Text(rows(level)),
+            &Content::builtin(),
+            &mut MessageQueues::new(),
+        )
+    }
+
+    #[test]
+    fn the_player_arrives_next_to_stairs_an_npc_is_standing_on() {
+        let mut world = world(&["#####", "#<1.#", "#####"]);
+        let npc_id = *world.entity_store.npc.iter().next().unwrap();
+        world.commit(insert::coord(npc_id, Coord::new(1, 1)));
+        assert_eq!(
+            arrival_coord(&world, LevelChange::Down),
+            Some(Coord::new(2, 1))
+        );
+    }
+
+    #[test]
+    fn there_is_no_arrival_without_stairs() {
+        let world = world(&["#####", "#...#", "#####"]);
+        assert_eq!(arrival_coord(&world, LevelChange::Down), None);
+    }
+
+    #[test]
+    fn an_invalid_level_is_replaced_by_a_fallback() {
+        // no up stairs to arrive on
+        let content = Content::builtin()
+            .with_level(0, rows(&["#####", "#@.>#", "#####"]))
+            .with_level(1, rows(&["#####", "#...#", "#####"]));
+        let mut state = State::with_content(0, content);
+        state.change_level(LevelChange::Down, 1);
+        assert_eq!(state.dungeon.depth(), 1);
+        assert_eq!(state.player_coord(), Some(Coord::new(1, 1)));
+    }
+}
@@ -0,0 +1,335 @@
+use rand::{Rng, SeedableRng, StdRng};
+use super::vault::{self, Vault};
+
+pub const WIDTH: u32 = 15;
+pub const HEIGHT: u32 = 12;
+
+const MAX_ROOMS: usize = 6;
+const ROOM_ATTEMPTS: usize = 64;
+const MIN_ROOM_SIZE: i32 = 3;
+const MAX_ROOM_SIZE: i32 = 6;
+const VAULT_ATTEMPTS: usize = 32;
+
+#[derive(Clone, Copy)]
+struct Room {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Room {
+    fn centre(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    fn overlaps(&self, other: &Room) -> bool {
+        // rooms are kept at least one wall apart
+        self.x <= other.x + other.width && other.x <= self.x + self.width
+            && self.y <= other.y + other.height && other.y <= self.y + self.height
+    }
+}
+
+#[derive(Clone)]
+struct Map {
+    cells: Vec<Vec<char>>,
+}
+
+impl Map {
+    fn new() -> Self {
+        Self {
+            cells: vec![vec!['#'; WIDTH as usize]; HEIGHT as usize],
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32, ch: char) {
+        self.cells[y as usize][x as usize] = ch;
+    }
+
+    fn get(&self, x: i32, y: i32) -> char {
+        self.cells[y as usize][x as usize]
+    }
+
+    fn carve_room(&mut self, room: &Room) {
+        for y in room.y..(room.y + room.height) {
+            for x in room.x..(room.x + room.width) {
+                self.set(x, y, '.');
+            }
+        }
+    }
+
+    fn carve_corridor(&mut self, (ax, ay): (i32, i32), (bx, by): (i32, i32)) {
+        for x in ax.min(bx)..(ax.max(bx) + 1) {
+            self.set(x, ay, '.');
+        }
+        for y in ay.min(by)..(ay.max(by) + 1) {
+            self.set(bx, y, '.');
+        }
+    }
+
+    fn into_strings(self) -> Vec<String> {
+        self.cells
+            .into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
+    }
+}
+
+fn random_room<R: Rng>(rng: &mut R) -> Room {
+    let width = rng.gen_range(MIN_ROOM_SIZE, MAX_ROOM_SIZE + 1);
+    let height = rng.gen_range(MIN_ROOM_SIZE, MAX_ROOM_SIZE + 1);
+    Room {
+        x: rng.gen_range(1, WIDTH as i32 - width),
+        y: rng.gen_range(1, HEIGHT as i32 - height),
+        width,
+        height,
+    }
+}
+
+fn free_cells(map: &Map, room: &Room) -> Vec<(i32, i32)> {
+    let mut cells = Vec::new();
+    for y in room.y..(room.y + room.height) {
+        for x in room.x..(room.x + room.width) {
+            if map.get(x, y) == '.' {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+fn vault_fits(map: &Map, vault: &Vault, x: i32, y: i32) -> bool {
+    if x < 1 || y < 1 || x + vault.width() > WIDTH as i32 - 1
+        || y + vault.height() > HEIGHT as i32 - 1
+    {
+        return false;
+    }
+    // the corridor starts at the doorstep, so it must be clear of the border for the
+    // corridor not to run along the edge of the map
+    let (dx, dy) = vault.doorstep();
+    let doorstep = (x + dx, y + dy);
+    if doorstep.0 < 2 || doorstep.1 < 2 || doorstep.0 > WIDTH as i32 - 3
+        || doorstep.1 > HEIGHT as i32 - 3
+    {
+        return false;
+    }
+    // vaults are surrounded by rock so they never open straight into a room
+    for j in (y - 1)..(y + vault.height() + 1) {
+        for i in (x - 1)..(x + vault.width() + 1) {
+            if map.get(i, j) != '#' {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn is_connected(map: &Map) -> bool {
+    let mut open = Vec::new();
+    for y in 0..HEIGHT as i32 {
+        for x in 0..WIDTH as i32 {
+            if map.get(x, y) != '#' {
+                open.push((x, y));
+            }
+        }
+    }
+    let start = if let Some(&start) = open.first() {
+        start
+    } else {
+        return true;
+    };
+    let mut seen = vec![vec![false; WIDTH as usize]; HEIGHT as usize];
+    let mut stack = vec![start];
+    seen[start.1 as usize][start.0 as usize] = true;
+    let mut count = 0;
+    while let Some((x, y)) = stack.pop() {
+        count += 1;
+        for &(dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)].iter() {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= WIDTH as i32 || ny >= HEIGHT as i32 {
+                continue;
+            }
+            if map.get(nx, ny) != '#' && !seen[ny as usize][nx as usize] {
+                seen[ny as usize][nx as usize] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+    count == open.len()
+}
+
+fn place_vault<R: Rng>(map: &mut Map, rooms: &[Room], rng: &mut R) {
+    for _ in 0..VAULT_ATTEMPTS {
+        let vault = Vault::random(rng);
+        let x = rng.gen_range(1, WIDTH as i32 - vault.width());
+        let y = rng.gen_range(1, HEIGHT as i32 - vault.height());
+        if !vault_fits(map, &vault, x, y) {
+            continue;
+        }
+
+        let mut candidate = map.clone();
+        let mut stamped = Vec::new();
+        for (j, row) in vault.cells.iter().enumerate() {
+            for (i, &ch) in row.iter().enumerate() {
+                let ch = if ch == vault::ENTRANCE { '.' } else { ch };
+                let (cx, cy) = (x + i as i32, y + j as i32);
+                candidate.set(cx, cy, ch);
+                stamped.push((cx, cy, ch));
+            }
+        }
+
+        let (dx, dy) = vault.doorstep();
+        let doorstep = (x + dx, y + dy);
+        let distance = |(ax, ay): (i32, i32)| (ax - doorstep.0).abs() + (ay - doorstep.1).abs();
+        let target = rooms
+            .iter()
+            .map(Room::centre)
+            .min_by_key(|&centre| distance(centre))
+            .expect("No rooms");
+        candidate.carve_corridor(doorstep, target);
+
+        // reject corridors that cut through the vault's walls
+        let intact = stamped
+            .iter()
+            .all(|&(cx, cy, ch)| candidate.get(cx, cy) == ch);
+
+        if intact && is_connected(&candidate) {
+            *map = candidate;
+            return;
+        }
+    }
+}
+
+fn enemy<R: Rng>(depth: usize, rng: &mut R) -> char {
+    // deeper floors draw from a wider range of enemies
+    match rng.gen_range(0, depth + 1) {
+        0 | 1 => '1',
+        2 => '2',
+        _ => *rng.choose(&['3', '4']).expect("No enemies to choose from"),
+    }
+}
+
+pub fn generate(seed: usize, depth: usize) -> Vec<String> {
+    let mut rng = StdRng::from_seed(&[seed, depth]);
+    let mut map = Map::new();
+    let mut rooms: Vec<Room> = Vec::new();
+
+    for _ in 0..ROOM_ATTEMPTS {
+        if rooms.len() >= MAX_ROOMS {
+            break;
+        }
+        let room = random_room(&mut rng);
+        if rooms.iter().any(|other| room.overlaps(other)) {
+            continue;
+        }
+        map.carve_room(&room);
+        if let Some(previous) = rooms.last() {
+            map.carve_corridor(previous.centre(), room.centre());
+        }
+        rooms.push(room);
+    }
+
+    place_vault(&mut map, &rooms, &mut rng);
+
+    let first = rooms[0];
+    let last = rooms[rooms.len() - 1];
+
+    let (x, y) = first.centre();
+    map.set(x, y, '<');
+
+    let mut cells = free_cells(&map, &last);
+    let index = rng.gen_range(0, cells.len());
+    let (x, y) = cells.swap_remove(index);
+    map.set(x, y, '>');
+
+    let mut cells = Vec::new();
+    for room in rooms.iter().skip(1) {
+        cells.extend(free_cells(&map, room));
+    }
+    rng.shuffle(&mut cells);
+
+    let num_enemies = 2 + depth;
+    let num_cards = 1 + rng.gen_range(0, 2);
+
+    for _ in 0..num_enemies {
+        if let Some((x, y)) = cells.pop() {
+            let ch = enemy(depth, &mut rng);
+            map.set(x, y, ch);
+        }
+    }
+
+    for _ in 0..num_cards {
+        if let Some((x, y)) = cells.pop() {
+            map.set(x, y, 'm');
+        }
+    }
+
+    map.into_strings()
+}
+
+#[cfg(test)]
+mod tests {
+    use grid_2d::Coord;
+    use content::Content;
+    use levels::FINAL_DEPTH;
+    use message_queues::MessageQueues;
+    use terrain::TerrainType;
+    use validate::{self, Arrival, Exit};
+    use world::World;
+    use super::*;
+
+    const SEEDS: usize = 32;
+
+    #[test]
+    fn generated_levels_are_connected() {
+        for seed in 0..SEEDS {
+            for depth in 1..FINAL_DEPTH {
+                let map = Map {
+                    cells: generate(seed, depth)
+                        .iter()
+                        .map(|row| row.chars().collect())
+                        .collect(),
+                };
+                assert!(is_connected(&map), "seed {} depth {}", seed, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_levels_are_valid() {
+        let content = Content::builtin();
+        for seed in 0..SEEDS {
+            for depth in 1..FINAL_DEPTH {
+                let terrain = TerrainType::Text(generate(seed, depth));
+                let world = World::new(&terrain, &content, &mut MessageQueues::new());
+                let exit = Exit::for_depth(depth, FINAL_DEPTH);
+                assert_eq!(
+                    validate::validate_world(&world, Arrival::UpStairs, exit),
+                    Ok(()),
+                    "seed {} depth {}",
+                    seed,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vaults_are_only_placed_away_from_the_border() {
+        let map = Map::new();
+        let mut rng = StdRng::from_seed(&[0]);
+        for _ in 0..64 {
+            let vault = Vault::random(&mut rng);
+            for y in 0..HEIGHT as i32 {
+                for x in 0..WIDTH as i32 {
+                    if vault_fits(&map, &vault, x, y) {
+                        let (dx, dy) = vault.doorstep();
+                        let doorstep = Coord::new(x + dx, y + dy);
+                        assert!(doorstep.x >= 2 && doorstep.x <= WIDTH as i32 - 3);
+                        assert!(doorstep.y >= 2 && doorstep.y <= HEIGHT as i32 - 3);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,22 @@
 use grid_2d::*;
 use entity_store::EntityIdAllocator;
 use message_queues::*;
+use content::Content;
 
 mod static_strings;
+mod generate;
+mod vault;
+
+pub const GLYPHS: &'static str = "#.mj01234EC<>@";
+
+// no level may be larger than a generated one
+pub const MAX_WIDTH: u32 = generate::WIDTH;
+pub const MAX_HEIGHT: u32 = generate::HEIGHT;
 
 pub enum TerrainType {
     StaticStrings(Vec<&'static str>),
+    Text(Vec<String>),
+    Generated { seed: usize, depth: usize },
 }
 
 impl TerrainType {
@@ -14,17 +25,29 @@ impl TerrainType {
             &TerrainType::StaticStrings(ref strings) => {
                 Size::new(strings[0].len() as u32, strings.len() as u32)
             }
+            &TerrainType::Text(ref strings) => {
+                Size::new(strings[0].chars().count() as u32, strings.len() as u32)
+            }
+            &TerrainType::Generated { .. } => Size::new(generate::WIDTH, generate::HEIGHT),
         }
     }
 
     pub fn populate(
         &self,
+        content: &Content,
         id_allocator: &mut EntityIdAllocator,
         messages: &mut MessageQueues,
     ) {
         match self {
             &TerrainType::StaticStrings(ref strings) => {
-                static_strings::populate(strings, id_allocator, messages);
+                static_strings::populate(strings, content, id_allocator, messages);
+            }
+            &TerrainType::Text(ref strings) => {
+                static_strings::populate(strings, content, id_allocator, messages);
+            }
+            &TerrainType::Generated { seed, depth } => {
+                let strings = generate::generate(seed, depth);
+                static_strings::populate(&strings, content, id_allocator, messages);
             }
         }
     }
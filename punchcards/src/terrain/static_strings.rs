@@ -4,14 +4,17 @@ use message_queues::*;
 use prototypes;
 use card::Card;
 use tile::Tile;
+use content::Content;
+use script;
 
-pub fn populate(
-    strings: &Vec<&'static str>,
+pub fn populate<S: AsRef<str>>(
+    strings: &[S],
+    content: &Content,
     id_allocator: &mut EntityIdAllocator,
     messages: &mut MessageQueues,
 ) {
     for (y, line) in strings.iter().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
+        for (x, ch) in line.as_ref().chars().enumerate() {
             let coord = Coord::new(x as i32, y as i32);
             match ch {
                 '#' => {
@@ -31,6 +34,20 @@ pub fn populate(
                     );
                     prototypes::floor(id_allocator.allocate(), coord, messages);
                 }
+                'j' => {
+                    prototypes::card(
+                        id_allocator.allocate(),
+                        coord,
+                        Card::Scripted(script::JAB),
+                        Tile::CardSpecial,
+                        messages,
+                    );
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
+                'E' => {
+                    prototypes::elite_robot(id_allocator.allocate(), coord, messages);
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
                 '0' => {
                     prototypes::target_dummy(id_allocator.allocate(), coord, messages);
                     prototypes::floor(id_allocator.allocate(), coord, messages);
@@ -42,12 +59,43 @@ pub fn populate(
                 '>' => {
                     prototypes::stairs(id_allocator.allocate(), coord, messages);
                 }
+                '<' => {
+                    prototypes::up_stairs(id_allocator.allocate(), coord, messages);
+                }
+                '2' => {
+                    prototypes::gun_robot(id_allocator.allocate(), coord, messages);
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
+                '3' => {
+                    prototypes::guard(id_allocator.allocate(), coord, messages);
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
+                '4' => {
+                    prototypes::skirmisher(id_allocator.allocate(), coord, messages);
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
+                'C' => {
+                    prototypes::champion(id_allocator.allocate(), coord, messages);
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
                 '@' => {
                     let id = id_allocator.allocate();
                     prototypes::player(id, coord, messages);
                     prototypes::floor(id_allocator.allocate(), coord, messages);
                 }
-                _ => panic!("unexpected character"),
+                other => {
+                    let index = content
+                        .enemy_by_glyph(other)
+                        .expect("unexpected character");
+                    prototypes::pack_enemy(
+                        id_allocator.allocate(),
+                        coord,
+                        index,
+                        content.enemy(index),
+                        messages,
+                    );
+                    prototypes::floor(id_allocator.allocate(), coord, messages);
+                }
             }
         }
     }
@@ -0,0 +1,163 @@
+use rand::Rng;
+
+// '+' marks the entrance, which must sit on the edge of the vault
+const VAULTS: &'static [&'static [&'static str]] = &[
+    &[
+        "#####",
+        "#.j.#",
+        "#...#",
+        "##+##",
+    ],
+    &[
+        "######",
+        "#E..j#",
+        "#.##.#",
+        "#+####",
+    ],
+    &[
+        "#######",
+        "#m.E.m#",
+        "###+###",
+    ],
+];
+
+pub const ENTRANCE: char = '+';
+
+#[derive(Clone)]
+pub struct Vault {
+    pub cells: Vec<Vec<char>>,
+}
+
+impl Vault {
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        let rows = rng.choose(VAULTS).expect("No vaults");
+        let mut vault = Vault {
+            cells: rows.iter().map(|row| row.chars().collect()).collect(),
+        };
+        for _ in 0..rng.gen_range(0, 4) {
+            vault = vault.rotate();
+        }
+        if rng.gen() {
+            vault = vault.mirror();
+        }
+        vault
+    }
+
+    pub fn width(&self) -> i32 {
+        self.cells[0].len() as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.cells.len() as i32
+    }
+
+    // quarter turn clockwise
+    fn rotate(&self) -> Self {
+        let height = self.cells.len();
+        let width = self.cells[0].len();
+        let cells = (0..width)
+            .map(|x| (0..height).rev().map(|y| self.cells[y][x]).collect())
+            .collect();
+        Vault { cells }
+    }
+
+    fn mirror(&self) -> Self {
+        let cells = self.cells
+            .iter()
+            .map(|row| row.iter().rev().cloned().collect())
+            .collect();
+        Vault { cells }
+    }
+
+    pub fn entrance(&self) -> (i32, i32) {
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &ch) in row.iter().enumerate() {
+                if ch == ENTRANCE {
+                    return (x as i32, y as i32);
+                }
+            }
+        }
+        panic!("Vault has no entrance");
+    }
+
+    // the cell just outside the entrance, where the connecting corridor starts
+    pub fn doorstep(&self) -> (i32, i32) {
+        let (x, y) = self.entrance();
+        if y == 0 {
+            (x, y - 1)
+        } else if y == self.height() - 1 {
+            (x, y + 1)
+        } else if x == 0 {
+            (x - 1, y)
+        } else {
+            (x + 1, y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vaults() -> Vec<Vault> {
+        VAULTS
+            .iter()
+            .map(|rows| Vault {
+                cells: rows.iter().map(|row| row.chars().collect()).collect(),
+            })
+            .collect()
+    }
+
+    fn entrances(vault: &Vault) -> usize {
+        vault
+            .cells
+            .iter()
+            .map(|row| row.iter().filter(|&&ch| ch == ENTRANCE).count())
+            .sum()
+    }
+
+    // the doorstep is just outside the vault, next to its only entrance
+    fn assert_door(vault: &Vault) {
+        assert_eq!(entrances(vault), 1);
+        let (ex, ey) = vault.entrance();
+        let (dx, dy) = vault.doorstep();
+        assert_eq!((ex - dx).abs() + (ey - dy).abs(), 1);
+        assert!(dx < 0 || dy < 0 || dx >= vault.width() || dy >= vault.height());
+    }
+
+    #[test]
+    fn four_rotations_give_back_the_original() {
+        for vault in vaults() {
+            let rotated = vault.rotate().rotate().rotate().rotate();
+            assert_eq!(rotated.cells, vault.cells);
+        }
+    }
+
+    #[test]
+    fn rotating_swaps_width_and_height() {
+        for vault in vaults() {
+            let rotated = vault.rotate();
+            assert_eq!(rotated.width(), vault.height());
+            assert_eq!(rotated.height(), vault.width());
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_gives_back_the_original() {
+        for vault in vaults() {
+            assert_eq!(vault.mirror().mirror().cells, vault.cells);
+        }
+    }
+
+    #[test]
+    fn rotated_and_mirrored_vaults_keep_their_door() {
+        for vault in vaults() {
+            let mut vault = vault;
+            for _ in 0..4 {
+                assert_door(&vault);
+                assert_door(&vault.mirror());
+                vault = vault.rotate();
+            }
+        }
+    }
+}
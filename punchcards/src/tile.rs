@@ -6,9 +6,43 @@ pub enum Tile {
     Wall,
     Floor,
     CardMove,
+    CardSpecial,
     Punch(CardinalDirection),
     TargetDummy,
     SmallRobot,
+    EliteRobot,
+    GunRobot,
+    Guard,
+    Skirmisher,
+    PackEnemy(u32),
+    Champion(u32),
     Stairs,
+    UpStairs,
     Bullet,
 }
+
+impl Tile {
+    // a glyph for every tile that any terminal can show, with no two tiles sharing one
+    pub fn ascii_glyph(self) -> char {
+        match self {
+            Tile::Player => '@',
+            Tile::Wall => '#',
+            Tile::Floor => '.',
+            Tile::CardMove => 'm',
+            Tile::CardSpecial => 'j',
+            Tile::Punch(CardinalDirection::North) | Tile::Punch(CardinalDirection::South) => '|',
+            Tile::Punch(CardinalDirection::East) | Tile::Punch(CardinalDirection::West) => '-',
+            Tile::TargetDummy => '0',
+            Tile::SmallRobot => '1',
+            Tile::GunRobot => '2',
+            Tile::Guard => '3',
+            Tile::Skirmisher => '4',
+            Tile::EliteRobot => 'E',
+            Tile::PackEnemy(_) => '?',
+            Tile::Champion(_) => 'C',
+            Tile::Stairs => '>',
+            Tile::UpStairs => '<',
+            Tile::Bullet => '*',
+        }
+    }
+}
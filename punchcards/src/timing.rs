@@ -1,2 +1,3 @@
 pub const PUNCH_MILLIS: u64        = 200;
 pub const BULLET_SLIDE_MILLIS: u64 = 50;
+pub const CHARGE_SLIDE_MILLIS: u64 = 80;
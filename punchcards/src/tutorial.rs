@@ -0,0 +1,256 @@
+use grid_2d::Coord;
+use card::Card;
+use card_state::CardState;
+use input::Input;
+use event::GameplayEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Trigger {
+    Immediately,
+    EnterCell(i32, i32),
+    CardTaken,
+    NpcDamaged,
+    NpcDestroyed,
+    Depth(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExpectedInput {
+    SelectCard(Card),
+    Direction,
+    Any,
+}
+
+struct Step {
+    trigger: Trigger,
+    expected: ExpectedInput,
+    prompt: &'static [&'static str],
+    // how many steps, this one included, are passed over if the expected card isn't in hand
+    skip: usize,
+}
+
+const STEPS: &'static [Step] = &[
+    Step {
+        trigger: Trigger::Immediately,
+        expected: ExpectedInput::SelectCard(Card::Move),
+        prompt: &[
+            "Every action is a card.",
+            "Press the number next to",
+            "a Move card.",
+        ],
+        skip: 2,
+    },
+    Step {
+        trigger: Trigger::Immediately,
+        expected: ExpectedInput::Direction,
+        prompt: &["Press an arrow key to", "move in that direction."],
+        skip: 0,
+    },
+    Step {
+        trigger: Trigger::CardTaken,
+        expected: ExpectedInput::Any,
+        prompt: &["You picked up a card!", "It was shuffled into", "your deck."],
+        skip: 0,
+    },
+    Step {
+        trigger: Trigger::EnterCell(4, 3),
+        expected: ExpectedInput::SelectCard(Card::Punch),
+        prompt: &["A target dummy (0) is in", "the way. Select a Punch", "card."],
+        skip: 3,
+    },
+    Step {
+        trigger: Trigger::Immediately,
+        expected: ExpectedInput::Direction,
+        prompt: &["Punch towards the dummy."],
+        skip: 0,
+    },
+    Step {
+        trigger: Trigger::NpcDamaged,
+        expected: ExpectedInput::Any,
+        prompt: &["Nice hit! Finish it off", "and take the stairs (>)."],
+        skip: 0,
+    },
+    Step {
+        trigger: Trigger::Depth(1),
+        expected: ExpectedInput::SelectCard(Card::Shoot),
+        prompt: &["Robots hunt you down.", "Select a Shoot card."],
+        skip: 3,
+    },
+    Step {
+        trigger: Trigger::Immediately,
+        expected: ExpectedInput::Direction,
+        prompt: &["Shoot at the robot (1)."],
+        skip: 0,
+    },
+    Step {
+        trigger: Trigger::NpcDestroyed,
+        expected: ExpectedInput::Any,
+        prompt: &["Well done! Mind your", "hand: if it runs out,", "the game is over."],
+        skip: 0,
+    },
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tutorial {
+    step: usize,
+    prompting: bool,
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self {
+            step: 0,
+            prompting: false,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    pub fn prompt(&self) -> Option<&'static [&'static str]> {
+        if self.prompting {
+            STEPS.get(self.step).map(|step| step.prompt)
+        } else {
+            None
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        player_coord: Coord,
+        depth: usize,
+        events: &[GameplayEvent],
+        card_state: &CardState,
+    ) {
+        while !self.prompting {
+            let step = if let Some(step) = STEPS.get(self.step) {
+                step
+            } else {
+                return;
+            };
+            let triggered = match step.trigger {
+                Trigger::Immediately => true,
+                Trigger::EnterCell(x, y) => player_coord == Coord::new(x, y),
+                Trigger::CardTaken => events.iter().any(|event| match event {
+                    &GameplayEvent::CardTaken(_) => true,
+                    _ => false,
+                }),
+                Trigger::NpcDamaged => events.contains(&GameplayEvent::NpcDamaged),
+                Trigger::NpcDestroyed => events.contains(&GameplayEvent::NpcDestroyed),
+                Trigger::Depth(trigger_depth) => depth == trigger_depth,
+            };
+            if !triggered {
+                return;
+            }
+            match step.expected {
+                // waiting for a card that was already spent would leave the player stuck
+                ExpectedInput::SelectCard(card) if !card_state.hand.iter().any(|c| *c == Some(card)) => {
+                    self.step += step.skip;
+                }
+                _ => self.prompting = true,
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        self.prompting = false;
+        self.step += 1;
+    }
+
+    // a direction is let through, but the step only advances once the play is accepted
+    pub fn accept(&mut self, input: &Input, card_state: &CardState) -> bool {
+        if !self.prompting {
+            return true;
+        }
+        let expected = STEPS[self.step].expected;
+        let (accepted, advance) = match (expected, input) {
+            (ExpectedInput::SelectCard(card), &Input::SelectCard(index)) => {
+                let accepted = card_state.hand.get(index) == Some(&card);
+                (accepted, accepted)
+            }
+            (ExpectedInput::Direction, &Input::Direction(_)) => (true, false),
+            (ExpectedInput::Any, _) => (true, true),
+            _ => (false, false),
+        };
+        if advance {
+            self.advance();
+        }
+        accepted
+    }
+
+    pub fn card_played(&mut self) {
+        if self.prompting && STEPS[self.step].expected == ExpectedInput::Direction {
+            self.advance();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use direction::CardinalDirection;
+    use super::*;
+
+    const PUNCH_LESSON: usize = 3;
+
+    fn card_state(cards: Vec<Card>) -> CardState {
+        CardState::unshuffled(cards, 4)
+    }
+
+    fn at_step(step: usize) -> Tutorial {
+        Tutorial {
+            step,
+            prompting: false,
+        }
+    }
+
+    #[test]
+    fn the_punch_lesson_prompts_when_a_punch_is_in_hand() {
+        let mut tutorial = at_step(PUNCH_LESSON);
+        let card_state = card_state(vec![Card::Move, Card::Punch]);
+        tutorial.update(Coord::new(4, 3), 0, &[], &card_state);
+        assert_eq!(tutorial.prompt(), Some(STEPS[PUNCH_LESSON].prompt));
+    }
+
+    #[test]
+    fn the_punch_lesson_is_skipped_without_a_punch_in_hand() {
+        let mut tutorial = at_step(PUNCH_LESSON);
+        let card_state = card_state(vec![Card::Move, Card::Shoot]);
+        tutorial.update(Coord::new(4, 3), 0, &[], &card_state);
+        assert_eq!(tutorial.prompt(), None);
+        assert_eq!(tutorial.step, PUNCH_LESSON + STEPS[PUNCH_LESSON].skip);
+
+        // the next lesson still starts on reaching the next level
+        tutorial.update(Coord::new(1, 1), 1, &[], &card_state);
+        assert_eq!(tutorial.prompt(), Some(STEPS[PUNCH_LESSON + 3].prompt));
+    }
+
+    #[test]
+    fn only_the_expected_card_is_accepted() {
+        let mut tutorial = at_step(PUNCH_LESSON);
+        let card_state = card_state(vec![Card::Punch, Card::Move]);
+        tutorial.update(Coord::new(4, 3), 0, &[], &card_state);
+
+        // cards are drawn from the end of the deck
+        assert!(!tutorial.accept(&Input::SelectCard(0), &card_state));
+        assert!(!tutorial.accept(&Input::Wait, &card_state));
+        assert!(tutorial.accept(&Input::SelectCard(1), &card_state));
+        assert_eq!(tutorial.step, PUNCH_LESSON + 1);
+    }
+
+    #[test]
+    fn a_direction_step_waits_for_the_play_to_be_accepted() {
+        let mut tutorial = at_step(PUNCH_LESSON + 1);
+        let card_state = card_state(vec![Card::Punch]);
+        tutorial.update(Coord::new(4, 3), 0, &[], &card_state);
+
+        let input = Input::Direction(CardinalDirection::East);
+        assert!(tutorial.accept(&input, &card_state));
+        assert_eq!(tutorial.step, PUNCH_LESSON + 1);
+        assert!(tutorial.prompt().is_some());
+
+        tutorial.card_played();
+        assert_eq!(tutorial.step, PUNCH_LESSON + 2);
+        assert_eq!(tutorial.prompt(), None);
+    }
+}
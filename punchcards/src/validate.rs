@@ -0,0 +1,331 @@
+use std::fmt;
+use grid_2d::Coord;
+use world::World;
+use terrain::{self, TerrainType};
+use content::Content;
+use message_queues::MessageQueues;
+use pathfinding::PathfindingContext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrival {
+    Player,
+    UpStairs,
+}
+
+impl Arrival {
+    pub fn for_depth(depth: usize) -> Self {
+        if depth == 0 {
+            Arrival::Player
+        } else {
+            Arrival::UpStairs
+        }
+    }
+}
+
+// every floor but the last needs a way down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Required,
+    Optional,
+}
+
+impl Exit {
+    pub fn for_depth(depth: usize, final_depth: usize) -> Self {
+        if depth < final_depth {
+            Exit::Required
+        } else {
+            Exit::Optional
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelError {
+    Empty,
+    TooLarge,
+    NotRectangular { row: usize },
+    UnknownGlyph { coord: Coord, glyph: char },
+    MissingArrival,
+    MultipleArrivals(usize),
+    UnexpectedPlayer,
+    MissingExit,
+    OpenBorder(Coord),
+    UnreachableStairs(Coord),
+    OverlappingEntities(Coord),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &LevelError::Empty => write!(f, "Level is empty"),
+            &LevelError::TooLarge => write!(
+                f,
+                "Level is larger than {}x{}",
+                terrain::MAX_WIDTH,
+                terrain::MAX_HEIGHT
+            ),
+            &LevelError::NotRectangular { row } => write!(f, "Row {} has wrong width", row + 1),
+            &LevelError::UnknownGlyph { coord, glyph } => {
+                write!(f, "Unknown '{}' at {},{}", glyph, coord.x, coord.y)
+            }
+            &LevelError::MissingArrival => write!(f, "No way into level"),
+            &LevelError::MultipleArrivals(count) => write!(f, "{} ways into level", count),
+            &LevelError::UnexpectedPlayer => write!(f, "Player on a lower floor"),
+            &LevelError::MissingExit => write!(f, "No way down"),
+            &LevelError::OpenBorder(coord) => write!(f, "Open edge at {},{}", coord.x, coord.y),
+            &LevelError::UnreachableStairs(coord) => {
+                write!(f, "Stairs at {},{} unreachable", coord.x, coord.y)
+            }
+            &LevelError::OverlappingEntities(coord) => {
+                write!(f, "Overlap at {},{}", coord.x, coord.y)
+            }
+        }
+    }
+}
+
+impl LevelError {
+    // the row of the level the error was found on, if there is one
+    pub fn row(&self) -> Option<usize> {
+        match self {
+            &LevelError::NotRectangular { row } => Some(row),
+            &LevelError::UnknownGlyph { coord, .. }
+            | &LevelError::OpenBorder(coord)
+            | &LevelError::UnreachableStairs(coord)
+            | &LevelError::OverlappingEntities(coord) => Some(coord.y as usize),
+            _ => None,
+        }
+    }
+}
+
+fn start_coord(world: &World, arrival: Arrival) -> Result<Coord, LevelError> {
+    let entity_store = &world.entity_store;
+    let num_players = entity_store.player.iter().count();
+    let starts = match arrival {
+        Arrival::Player => entity_store.player.iter().collect::<Vec<_>>(),
+        Arrival::UpStairs => {
+            if num_players > 0 {
+                return Err(LevelError::UnexpectedPlayer);
+            }
+            entity_store.up_stairs.iter().collect::<Vec<_>>()
+        }
+    };
+    match starts.len() {
+        0 => Err(LevelError::MissingArrival),
+        1 => Ok(*entity_store
+            .coord
+            .get(starts[0])
+            .expect("Arrival missing coord")),
+        count => Err(LevelError::MultipleArrivals(count)),
+    }
+}
+
+pub fn validate_world(world: &World, arrival: Arrival, exit: Exit) -> Result<(), LevelError> {
+    let size = world.size();
+    for y in 0..size.height() as i32 {
+        for x in 0..size.width() as i32 {
+            let coord = Coord::new(x, y);
+            if let Some(cell) = world.spatial_hash.get(coord) {
+                let border = x == 0 || y == 0 || x == size.width() as i32 - 1
+                    || y == size.height() as i32 - 1;
+                if border && cell.solid_count == 0 {
+                    return Err(LevelError::OpenBorder(coord));
+                }
+                let occupants = cell.solid_count + cell.player_count + cell.npc_set.len()
+                    + cell.card_set.len() + cell.stairs_count
+                    + cell.up_stairs_count;
+                if occupants > 1 {
+                    return Err(LevelError::OverlappingEntities(coord));
+                }
+            }
+        }
+    }
+
+    let start = start_coord(world, arrival)?;
+
+    if exit == Exit::Required && world.entity_store.stairs.iter().next().is_none() {
+        return Err(LevelError::MissingExit);
+    }
+
+    let mut pathfinding = PathfindingContext::new(size);
+    pathfinding.update_player_map(start, &world.spatial_hash);
+
+    let entity_store = &world.entity_store;
+    let stairs = entity_store.stairs.iter().chain(entity_store.up_stairs.iter());
+    for id in stairs {
+        let coord = *entity_store.coord.get(id).expect("Stairs missing coord");
+        if pathfinding.distance_to_player(coord).is_none() {
+            return Err(LevelError::UnreachableStairs(coord));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_level<S: AsRef<str>>(
+    rows: &[S],
+    arrival: Arrival,
+    exit: Exit,
+    content: &Content,
+) -> Result<(), LevelError> {
+    let width = if let Some(row) = rows.first() {
+        row.as_ref().chars().count()
+    } else {
+        return Err(LevelError::Empty);
+    };
+    for (y, row) in rows.iter().enumerate() {
+        let row = row.as_ref();
+        if row.chars().count() != width {
+            return Err(LevelError::NotRectangular { row: y });
+        }
+        for (x, glyph) in row.chars().enumerate() {
+            if !terrain::GLYPHS.contains(glyph) && content.enemy_by_glyph(glyph).is_none() {
+                return Err(LevelError::UnknownGlyph {
+                    coord: Coord::new(x as i32, y as i32),
+                    glyph,
+                });
+            }
+        }
+    }
+    if width == 0 {
+        return Err(LevelError::Empty);
+    }
+    if width > terrain::MAX_WIDTH as usize || rows.len() > terrain::MAX_HEIGHT as usize {
+        return Err(LevelError::TooLarge);
+    }
+
+    let terrain = TerrainType::Text(rows.iter().map(|row| row.as_ref().to_string()).collect());
+    let world = World::new(&terrain, content, &mut MessageQueues::new());
+    validate_world(&world, arrival, exit)
+}
+
+#[cfg(test)]
+mod tests {
+    use entity_store::*;
+    use super::*;
+
+    fn level(rows: &[&str], arrival: Arrival, exit: Exit) -> Result<(), LevelError> {
+        validate_level(rows, arrival, exit, &Content::builtin())
+    }
+
+    fn first_level(rows: &[&str]) -> Result<(), LevelError> {
+        level(rows, Arrival::Player, Exit::Required)
+    }
+
+    #[test]
+    fn a_level_with_a_way_in_and_a_way_down_is_valid() {
+        assert_eq!(first_level(&["#####", "#@.>#", "#####"]), Ok(()));
+        assert_eq!(
+            level(&["#####", "#<.>#", "#####"], Arrival::UpStairs, Exit::Required),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(first_level(&[]), Err(LevelError::Empty));
+        assert_eq!(first_level(&["", ""]), Err(LevelError::Empty));
+    }
+
+    #[test]
+    fn too_large() {
+        let wide = "#".repeat(terrain::MAX_WIDTH as usize + 1);
+        assert_eq!(first_level(&[&wide[..]]), Err(LevelError::TooLarge));
+        let tall = vec!["#"; terrain::MAX_HEIGHT as usize + 1];
+        assert_eq!(first_level(&tall), Err(LevelError::TooLarge));
+    }
+
+    #[test]
+    fn not_rectangular() {
+        let error = first_level(&["#####", "#@>#", "#####"]).unwrap_err();
+        assert_eq!(error, LevelError::NotRectangular { row: 1 });
+        assert_eq!(error.row(), Some(1));
+    }
+
+    #[test]
+    fn unknown_glyph() {
+        assert_eq!(
+            first_level(&["#####", "#@?>#", "#####"]),
+            Err(LevelError::UnknownGlyph {
+                coord: Coord::new(2, 1),
+                glyph: '?',
+            })
+        );
+    }
+
+    #[test]
+    fn missing_arrival() {
+        assert_eq!(
+            first_level(&["#####", "#..>#", "#####"]),
+            Err(LevelError::MissingArrival)
+        );
+        assert_eq!(
+            level(&["#####", "#..>#", "#####"], Arrival::UpStairs, Exit::Required),
+            Err(LevelError::MissingArrival)
+        );
+    }
+
+    #[test]
+    fn multiple_arrivals() {
+        assert_eq!(
+            first_level(&["#####", "#@@>#", "#####"]),
+            Err(LevelError::MultipleArrivals(2))
+        );
+        assert_eq!(
+            level(&["#####", "#<<>#", "#####"], Arrival::UpStairs, Exit::Required),
+            Err(LevelError::MultipleArrivals(2))
+        );
+    }
+
+    #[test]
+    fn unexpected_player() {
+        assert_eq!(
+            level(&["######", "#<@.>#", "######"], Arrival::UpStairs, Exit::Required),
+            Err(LevelError::UnexpectedPlayer)
+        );
+    }
+
+    #[test]
+    fn missing_exit() {
+        let rows = ["#####", "#@..#", "#####"];
+        assert_eq!(first_level(&rows), Err(LevelError::MissingExit));
+        assert_eq!(level(&rows, Arrival::Player, Exit::Optional), Ok(()));
+    }
+
+    #[test]
+    fn open_border() {
+        assert_eq!(
+            first_level(&["#####", "#@.>.", "#####"]),
+            Err(LevelError::OpenBorder(Coord::new(4, 1)))
+        );
+        assert_eq!(
+            first_level(&["##.##", "#@.>#", "#####"]),
+            Err(LevelError::OpenBorder(Coord::new(2, 0)))
+        );
+    }
+
+    #[test]
+    fn unreachable_stairs() {
+        let error = first_level(&["#######", "#@#.>.#", "#######"]).unwrap_err();
+        assert_eq!(error, LevelError::UnreachableStairs(Coord::new(4, 1)));
+        assert_eq!(error.row(), Some(1));
+    }
+
+    #[test]
+    fn overlapping_entities() {
+        let terrain = TerrainType::Text(
+            vec!["######", "#@1m>#", "######"]
+                .into_iter()
+                .map(|row| row.to_string())
+                .collect(),
+        );
+        let mut world = World::new(&terrain, &Content::builtin(), &mut MessageQueues::new());
+        assert_eq!(validate_world(&world, Arrival::Player, Exit::Required), Ok(()));
+
+        let npc_id = *world.entity_store.npc.iter().next().unwrap();
+        world.commit(insert::coord(npc_id, Coord::new(3, 1)));
+        assert_eq!(
+            validate_world(&world, Arrival::Player, Exit::Required),
+            Err(LevelError::OverlappingEntities(Coord::new(3, 1)))
+        );
+    }
+}
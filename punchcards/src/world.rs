@@ -2,6 +2,15 @@ use entity_store::*;
 use grid_2d::*;
 use message_queues::*;
 use terrain::*;
+use content::Content;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldSaveState {
+    changes: Vec<EntityChange>,
+    id_allocator: EntityIdAllocator,
+    count: u64,
+    size: Size,
+}
 
 #[derive(Clone, Debug)]
 pub struct World {
@@ -13,7 +22,7 @@ pub struct World {
 }
 
 impl World {
-    pub fn new(terrain: &TerrainType, messages: &mut MessageQueues) -> Self {
+    pub fn new(terrain: &TerrainType, content: &Content, messages: &mut MessageQueues) -> Self {
         let size = terrain.size();
 
         let mut world = Self {
@@ -25,6 +34,7 @@ impl World {
         };
 
         terrain.populate(
+            content,
             &mut world.id_allocator,
             messages,
         );
@@ -36,6 +46,17 @@ impl World {
         world
     }
 
+    pub fn save(&self) -> WorldSaveState {
+        let mut changes = Vec::with_capacity(1024);
+        self.entity_store.clone_changes(&mut changes);
+        WorldSaveState {
+            changes,
+            id_allocator: self.id_allocator.clone(),
+            count: self.count,
+            size: self.size(),
+        }
+    }
+
     pub fn commit(&mut self, change: EntityChange) {
         self.spatial_hash.update(&self.entity_store, &change, 0);
         self.entity_components.update(&change);
@@ -50,3 +71,28 @@ impl World {
         self.entity_components.component_drain_insert(source_id, dest_id, &mut self.entity_store)
     }
 }
+
+impl From<WorldSaveState> for World {
+    fn from(
+        WorldSaveState {
+            mut changes,
+            id_allocator,
+            count,
+            size,
+        }: WorldSaveState,
+    ) -> Self {
+        let mut world = Self {
+            entity_store: EntityStore::new(),
+            spatial_hash: SpatialHashTable::new(size),
+            entity_components: EntityComponentTable::new(),
+            id_allocator,
+            count,
+        };
+
+        for change in changes.drain(..) {
+            world.commit(change);
+        }
+
+        world
+    }
+}
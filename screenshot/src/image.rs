@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+use png::{self, HasParameters};
+use rusttype::{point, Font, Scale};
+use prototty::{Coord, Rgb24, Size};
+use punchcards_prototty::cast::CastGrid;
+
+// same fonts and proportions as the glutin frontend
+const NORMAL_FONT: &'static [u8] = include_bytes!("../../glutin/src/fonts/PxPlus_IBM_CGAthin.ttf");
+const BOLD_FONT: &'static [u8] = include_bytes!("../../glutin/src/fonts/PxPlus_IBM_CGA.ttf");
+const UNDERLINE_POSITION: f32 = 28.0 / 32.0;
+const UNDERLINE_WIDTH: f32 = 2.0 / 32.0;
+
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 3) as usize],
+        }
+    }
+
+    fn blend(&mut self, x: i32, y: i32, colour: Rgb24, coverage: f32) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+        let index = ((y as u32 * self.width + x as u32) * 3) as usize;
+        let channels = [colour.red, colour.green, colour.blue];
+        for (pixel, &channel) in self.pixels[index..index + 3].iter_mut().zip(channels.iter()) {
+            *pixel = (*pixel as f32 * (1.0 - coverage) + channel as f32 * coverage) as u8;
+        }
+    }
+
+    fn fill(&mut self, x: i32, y: i32, width: i32, height: i32, colour: Rgb24) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.blend(px, py, colour, 1.0);
+            }
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, output: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(output, self.width, self.height);
+        encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+pub struct Rasterizer {
+    normal: Font<'static>,
+    bold: Font<'static>,
+    cell_size: u32,
+}
+
+impl Rasterizer {
+    pub fn new(cell_size: u32) -> Self {
+        Self {
+            normal: Font::from_bytes(NORMAL_FONT).expect("Failed to load font"),
+            bold: Font::from_bytes(BOLD_FONT).expect("Failed to load bold font"),
+            cell_size,
+        }
+    }
+
+    pub fn rasterize(&self, grid: &CastGrid) -> Image {
+        let size: Size = grid.size();
+        let cell_size = self.cell_size as i32;
+        let mut image = Image::new(size.width() * self.cell_size, size.height() * self.cell_size);
+        let scale = Scale::uniform(self.cell_size as f32);
+
+        for y in 0..size.height() as i32 {
+            for x in 0..size.width() as i32 {
+                let cell = match grid.get(Coord::new(x, y)) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                let left = x * cell_size;
+                let top = y * cell_size;
+
+                image.fill(left, top, cell_size, cell_size, cell.background());
+
+                let font = if cell.bold() { &self.bold } else { &self.normal };
+                let ascent = font.v_metrics(scale).ascent;
+                let glyph = font.glyph(cell.character())
+                    .scaled(scale)
+                    .positioned(point(left as f32, top as f32 + ascent));
+                if let Some(bounds) = glyph.pixel_bounding_box() {
+                    let foreground = cell.foreground();
+                    glyph.draw(|gx, gy, coverage| {
+                        image.blend(
+                            bounds.min.x + gx as i32,
+                            bounds.min.y + gy as i32,
+                            foreground,
+                            coverage,
+                        );
+                    });
+                }
+
+                if cell.underline() {
+                    let underline_top = top + (UNDERLINE_POSITION * cell_size as f32) as i32;
+                    let underline_height = ((UNDERLINE_WIDTH * cell_size as f32) as i32).max(1);
+                    image.fill(left, underline_top, cell_size, underline_height, cell.foreground());
+                }
+            }
+        }
+
+        image
+    }
+}
@@ -0,0 +1,175 @@
+extern crate png;
+extern crate prototty;
+extern crate prototty_file_storage;
+extern crate punchcards;
+extern crate punchcards_prototty;
+extern crate rusttype;
+extern crate serde_json;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::process;
+use prototty::{Size, Storage};
+use prototty_file_storage::FileStorage;
+use punchcards::state::SaveState;
+use punchcards_prototty::*;
+use punchcards_prototty::cast::CastGrid;
+use punchcards_prototty::session::load_replay;
+
+mod image;
+
+use image::Rasterizer;
+
+// the glutin frontend's grid and cell size
+const DEFAULT_WIDTH: u32 = 30;
+const DEFAULT_HEIGHT: u32 = 20;
+const DEFAULT_CELL_SIZE: u32 = 32;
+const SAVE_FILE: &'static str = "save";
+const SEED: usize = 0;
+
+const USAGE: &'static str = "\
+usage: punchcards_screenshot OUTPUT.png SOURCE [options]
+sources:
+  --user-dir DIR     the saved game in a user directory
+  --save-json FILE   a save written by the stdio frontend
+  --replay FILE      a replay, drawn at its last tick or at --tick
+options:
+  --tick N           stop the replay after N ticks
+  --width N          grid width in cells (default 30, or the replay's width)
+  --height N         grid height in cells (default 20, or the replay's height)
+  --cell-size N      pixels per cell (default 32)";
+
+enum Source {
+    UserDir(String),
+    SaveJson(String),
+    Replay { path: String, tick: Option<usize> },
+}
+
+struct Options {
+    output: String,
+    source: Source,
+    width: Option<u32>,
+    height: Option<u32>,
+    cell_size: u32,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(1);
+}
+
+fn number<T: ::std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.as_ref().map(|value| value.parse()) {
+        Some(Ok(number)) => number,
+        _ => fail(&format!("{} expects a number", flag)),
+    }
+}
+
+fn options() -> Options {
+    let mut args = env::args().skip(1);
+    let output = args.next().unwrap_or_else(|| fail("missing output file"));
+    let mut source = None;
+    let mut tick = None;
+    let mut width = None;
+    let mut height = None;
+    let mut cell_size = DEFAULT_CELL_SIZE;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--user-dir" | "--save-json" | "--replay" => {
+                let path = args.next()
+                    .unwrap_or_else(|| fail(&format!("{} expects a path", flag)));
+                source = Some(match flag.as_str() {
+                    "--user-dir" => Source::UserDir(path),
+                    "--save-json" => Source::SaveJson(path),
+                    _ => Source::Replay { path, tick: None },
+                });
+            }
+            "--tick" => tick = Some(number(&flag, args.next())),
+            "--width" => width = Some(number(&flag, args.next())),
+            "--height" => height = Some(number(&flag, args.next())),
+            "--cell-size" => cell_size = number(&flag, args.next()),
+            other => fail(&format!("unknown option \"{}\"", other)),
+        }
+    }
+    let source = match source {
+        Some(Source::Replay { path, .. }) => Source::Replay { path, tick },
+        Some(source) => source,
+        None => fail("missing source"),
+    };
+    Options {
+        output,
+        source,
+        width,
+        height,
+        cell_size,
+    }
+}
+
+// the app never saves, but a directory to load from is still needed
+fn scratch_storage() -> (FileStorage, ::std::path::PathBuf) {
+    let dir = env::temp_dir().join(format!("punchcards_screenshot_{}", process::id()));
+    let storage = FileStorage::new(&dir, true).expect("Failed to create scratch dir");
+    (storage, dir)
+}
+
+fn main() {
+    let options = options();
+
+    let (storage, scratch_dir) = scratch_storage();
+
+    let (app, size) = match options.source {
+        Source::UserDir(ref dir) => {
+            let user_storage = FileStorage::new(dir, false).expect("Failed to open user dir");
+            let save_state: SaveState = user_storage
+                .load(SAVE_FILE)
+                .unwrap_or_else(|_| fail("no saved game in user dir"));
+            let mut app = App::new(Frontend::Unix, storage, SEED);
+            app.show(save_state);
+            (app, Size::new(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+        }
+        Source::SaveJson(ref path) => {
+            let file = File::open(path).expect("Failed to open save");
+            let save_state: SaveState =
+                serde_json::from_reader(BufReader::new(file)).expect("Failed to read save");
+            let mut app = App::new(Frontend::Unix, storage, SEED);
+            app.show(save_state);
+            (app, Size::new(DEFAULT_WIDTH, DEFAULT_HEIGHT))
+        }
+        Source::Replay { ref path, tick } => {
+            let replay = load_replay(path)
+                .unwrap_or_else(|error| fail(&format!("Failed to read replay: {}", error)));
+            let mut app = App::new(Frontend::Unix, storage, replay.seed);
+            app.apply_settings(replay.settings);
+            if replay.new_game {
+                app.new_game();
+            }
+            let ticks = tick.unwrap_or(replay.ticks.len());
+            for replay_tick in replay.ticks.iter().take(ticks) {
+                if let Some(ControlFlow::Quit) =
+                    app.tick(replay_tick.inputs(), replay_tick.period())
+                {
+                    break;
+                }
+            }
+            (app, Size::new(replay.width, replay.height))
+        }
+    };
+
+    let size = Size::new(
+        options.width.unwrap_or(size.width()),
+        options.height.unwrap_or(size.height()),
+    );
+
+    let mut view = AppView::new(size);
+    let mut grid = CastGrid::new(size);
+    grid.render(&mut view, &app);
+
+    let image = Rasterizer::new(options.cell_size).rasterize(&grid);
+    let output = File::create(&options.output).expect("Failed to create output file");
+    image
+        .write_png(BufWriter::new(output))
+        .expect("Failed to write png");
+
+    let _ = fs::remove_dir_all(&scratch_dir);
+}
@@ -0,0 +1,285 @@
+extern crate direction;
+extern crate punchcards;
+extern crate punchcards_prototty;
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use rand::{Rng, SeedableRng, StdRng};
+use direction::CardinalDirection;
+use punchcards::state::{InputState, SaveState, State};
+use punchcards::input::Input;
+use punchcards::card::Card;
+use punchcards::intent::Intent;
+use punchcards::tile::Tile;
+use punchcards::tile_info::TileInfo;
+use punchcards::{ExternalEvent, GameplayEvent};
+use punchcards_prototty::cli::CommandLine;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl From<Direction> for CardinalDirection {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::North => CardinalDirection::North,
+            Direction::East => CardinalDirection::East,
+            Direction::South => CardinalDirection::South,
+            Direction::West => CardinalDirection::West,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Command {
+    NewGame { seed: usize },
+    SelectCard { index: usize },
+    Direction { direction: Direction },
+    Wait,
+    Save { path: String },
+    Load { path: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Playing,
+    GameOver,
+    Victory,
+}
+
+#[derive(Serialize)]
+struct IntentCell {
+    x: i32,
+    y: i32,
+    intent: Intent,
+}
+
+#[derive(Serialize)]
+struct Frame {
+    status: Status,
+    depth: usize,
+    grid: Vec<String>,
+    intents: Vec<IntentCell>,
+    hand: Vec<Option<String>>,
+    selected: Option<usize>,
+    deck: usize,
+    events: Vec<GameplayEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn glyph(tile_info: TileInfo, state: &State) -> char {
+    let content = state.content();
+    if let Tile::PackEnemy(index) = tile_info.tile {
+        return content.enemy(index).glyph;
+    }
+    if let Some(style) = content.tile_style(tile_info.tile) {
+        return style.glyph;
+    }
+    tile_info.tile.ascii_glyph()
+}
+
+fn card_name(card: Card, state: &State) -> String {
+    match card {
+        Card::Move => "Move".to_string(),
+        Card::Punch => "Punch".to_string(),
+        Card::Shoot => "Shoot".to_string(),
+        Card::Scripted(script_id) => state.scripts().name(script_id).to_string(),
+    }
+}
+
+fn frame(state: &State, status: Status, error: Option<String>) -> Frame {
+    let size = state.size();
+    let width = size.width() as usize;
+    let height = size.height() as usize;
+
+    // only the tile with the greatest depth in each cell is visible
+    let mut top: Vec<Option<TileInfo>> = vec![None; width * height];
+    let entity_store = state.entity_store();
+    for (id, tile_info) in entity_store.tile_info.iter() {
+        if let Some(coord) = entity_store.coord.get(&id) {
+            if coord.x < 0 || coord.y < 0 || coord.x as usize >= width
+                || coord.y as usize >= height
+            {
+                continue;
+            }
+            let slot = &mut top[coord.y as usize * width + coord.x as usize];
+            let visible = match *slot {
+                Some(current) => tile_info.depth >= current.depth,
+                None => true,
+            };
+            if visible {
+                *slot = Some(*tile_info);
+            }
+        }
+    }
+    let grid = top.chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|tile_info| tile_info.map(|tile_info| glyph(tile_info, state)).unwrap_or(' '))
+                .collect()
+        })
+        .collect();
+
+    let mut intents = Vec::new();
+    state.for_each_intent_cell(|coord, intent| {
+        intents.push(IntentCell {
+            x: coord.x,
+            y: coord.y,
+            intent,
+        });
+    });
+
+    let card_state = state.card_state();
+    let hand = card_state
+        .hand
+        .iter()
+        .map(|card| card.map(|card| card_name(card, state)))
+        .collect();
+    let selected = match *state.input_state() {
+        InputState::WaitingForDirection(index, _) => Some(index),
+        InputState::WaitingForCardSelection => None,
+    };
+
+    Frame {
+        status,
+        depth: state.depth(),
+        grid,
+        intents,
+        hand,
+        selected,
+        deck: card_state.deck.num_cards(),
+        events: state.gameplay_events().to_vec(),
+        error,
+    }
+}
+
+fn save(state: &State, path: &str, rng: &mut StdRng) -> Result<(), String> {
+    let save_state = state.save(rng.gen());
+    let json = serde_json::to_string(&save_state).map_err(|e| e.to_string())?;
+    File::create(path)
+        .and_then(|mut file| file.write_all(json.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+fn load(path: &str) -> Result<State, String> {
+    let mut json = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut json))
+        .map_err(|e| e.to_string())?;
+    let save_state: SaveState = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    // packs are never loaded here, so a save that needs one can't be played
+    if let Some(pack) = save_state.packs().first() {
+        return Err(format!("Save needs pack: {}", pack.name));
+    }
+    Ok(State::from(save_state))
+}
+
+fn write_frame<W: Write>(output: &mut W, frame: Frame) {
+    let json = serde_json::to_string(&frame).expect("Failed to serialize frame");
+    writeln!(output, "{}", json).expect("Failed to write frame");
+    output.flush().expect("Failed to flush output");
+}
+
+// the seed is given with --seed, or as the only other argument
+fn seed() -> Result<Option<usize>, String> {
+    let command_line = CommandLine::parse(env::args().skip(1))?;
+    let mut rest = command_line.rest.into_iter();
+    let positional = match rest.next() {
+        Some(seed) => {
+            let parsed: usize = seed
+                .parse()
+                .map_err(|_| format!("Seed must be a number, got \"{}\"", seed))?;
+            Some(parsed)
+        }
+        None => None,
+    };
+    if let Some(arg) = rest.next() {
+        return Err(format!("Unexpected argument \"{}\"", arg));
+    }
+    Ok(command_line.seed.or(positional))
+}
+
+fn main() {
+    // bad arguments are reported in the first frame, and a random seed is used instead
+    let (seed, error) = match seed() {
+        Ok(seed) => (seed.unwrap_or_else(|| rand::thread_rng().gen()), None),
+        Err(error) => (rand::thread_rng().gen(), Some(error)),
+    };
+
+    let mut rng = StdRng::from_seed(&[seed]);
+    let mut state = State::new(rng.gen());
+    let mut status = Status::Playing;
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    write_frame(&mut stdout, frame(&state, status, error));
+
+    for line in stdin.lock().lines() {
+        let line = line.expect("Failed to read stdin");
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let input = match serde_json::from_str::<Command>(&line) {
+            Ok(Command::NewGame { seed }) => {
+                state = State::new(seed);
+                status = Status::Playing;
+                None
+            }
+            Ok(Command::SelectCard { index }) => Some(Input::SelectCard(index)),
+            Ok(Command::Direction { direction }) => Some(Input::Direction(direction.into())),
+            Ok(Command::Wait) => Some(Input::Wait),
+            Ok(Command::Save { path }) => {
+                let error = save(&state, &path, &mut rng).err();
+                write_frame(&mut stdout, frame(&state, status, error));
+                continue;
+            }
+            Ok(Command::Load { path }) => {
+                match load(&path) {
+                    Ok(loaded) => {
+                        state = loaded;
+                        status = Status::Playing;
+                        write_frame(&mut stdout, frame(&state, status, None));
+                    }
+                    Err(error) => write_frame(&mut stdout, frame(&state, status, Some(error))),
+                }
+                continue;
+            }
+            Err(error) => {
+                write_frame(&mut stdout, frame(&state, status, Some(error.to_string())));
+                continue;
+            }
+        };
+
+        let error = match (input, status) {
+            (Some(input), Status::Playing) => {
+                match state.headless_turn(Some(input)) {
+                    Some(ExternalEvent::GameOver) => status = Status::GameOver,
+                    Some(ExternalEvent::Victory) => status = Status::Victory,
+                    _ => (),
+                }
+                None
+            }
+            (Some(_), _) => Some("Game has ended".to_string()),
+            (None, _) => None,
+        };
+
+        write_frame(&mut stdout, frame(&state, status, error));
+    }
+}
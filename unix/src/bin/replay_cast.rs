@@ -0,0 +1,56 @@
+extern crate prototty;
+extern crate prototty_file_storage;
+extern crate punchcards_prototty;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process;
+use std::time::Duration;
+use prototty::Size;
+use prototty_file_storage::FileStorage;
+use punchcards_prototty::*;
+use punchcards_prototty::cast::CastWriter;
+use punchcards_prototty::session::load_replay;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.len() != 2 {
+        eprintln!("usage: replay_cast REPLAY_FILE CAST_FILE");
+        process::exit(1);
+    }
+
+    let replay = match load_replay(&args[0]) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("Failed to read replay: {}", error);
+            process::exit(1);
+        }
+    };
+
+    // the replay is played from a fresh user directory so it can't touch real saves
+    let user_dir = env::temp_dir().join(format!("punchcards_replay_{}", process::id()));
+    let storage = FileStorage::new(&user_dir, true).expect("Failed to create user dir");
+
+    let size = Size::new(replay.width, replay.height);
+    let mut app = App::new(Frontend::Unix, storage, replay.seed);
+    app.apply_settings(replay.settings);
+    if replay.new_game {
+        app.new_game();
+    }
+    let mut view = AppView::new(size);
+
+    let cast_file = File::create(&args[1]).expect("Failed to create cast file");
+    let mut cast = CastWriter::new(BufWriter::new(cast_file), size).expect("Failed to write cast");
+
+    let mut time = Duration::default();
+    for tick in replay.ticks.iter() {
+        cast.frame(&mut view, &app, time).expect("Failed to write cast");
+        time += tick.period();
+        if let Some(ControlFlow::Quit) = app.tick(tick.inputs(), tick.period()) {
+            break;
+        }
+    }
+
+    let _ = fs::remove_dir_all(&user_dir);
+}
@@ -3,40 +3,210 @@ extern crate prototty_file_storage;
 extern crate prototty_unix;
 extern crate punchcards_prototty;
 extern crate rand;
+extern crate serde_json;
 
-use std::time::Duration;
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::mem;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::thread;
+use std::sync::mpsc::TryRecvError;
 use rand::Rng;
 use prototty_unix::Context;
 use prototty::Renderer;
+use prototty::Input as ProtottyInput;
+use prototty::inputs as prototty_inputs;
 use prototty_file_storage::FileStorage;
 use punchcards_prototty::*;
+use punchcards_prototty::cast::CastWriter;
+use punchcards_prototty::cli::{self, CommandLine};
+use punchcards_prototty::locations::Locations;
+use punchcards_prototty::session::Session;
+
+mod spectate;
 
-const USER_DIR: &'static str = "user";
 const TICK_MILLIS: u64 = 33;
 
-fn main() {
-    let storage = FileStorage::next_to_exe(USER_DIR, true).expect("Failed to find user dir");
+const USAGE: &'static str = "\
+usage: punchcards [options]
+  --serve [ADDRESS]      let other terminals watch this game
+  --watch [ADDRESS]      watch a game started with --serve
+  --record-cast FILE     record the screen as an asciicast";
+
+struct Options {
+    command_line: CommandLine,
+    serve: Option<String>,
+    watch: Option<String>,
+    record_cast: Option<String>,
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("{}\n{}", USAGE, cli::OPTIONS_HELP);
+    ::std::process::exit(1);
+}
+
+// an option's address may be left out to use the default
+fn address<I: Iterator<Item = String>>(args: &mut ::std::iter::Peekable<I>) -> String {
+    let explicit = match args.peek() {
+        Some(next) => !next.starts_with("--"),
+        None => false,
+    };
+    if explicit {
+        args.next().unwrap()
+    } else {
+        spectate::DEFAULT_ADDRESS.to_string()
+    }
+}
+
+fn options() -> Options {
+    let mut command_line =
+        CommandLine::parse(env::args().skip(1)).unwrap_or_else(|message| usage_error(&message));
+    let mut options = Options {
+        serve: None,
+        watch: None,
+        record_cast: None,
+        command_line: CommandLine::default(),
+    };
+    let mut args = mem::replace(&mut command_line.rest, Vec::new())
+        .into_iter()
+        .peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--serve" => options.serve = Some(address(&mut args)),
+            "--watch" => options.watch = Some(address(&mut args)),
+            "--record-cast" => match args.next() {
+                Some(path) => options.record_cast = Some(path),
+                None => usage_error("--record-cast needs a file"),
+            },
+            other => usage_error(&format!("Unknown option \"{}\"", other)),
+        }
+    }
+    options.command_line = command_line;
+    options
+}
+
+fn app(command_line: &CommandLine, seed: usize) -> App<FileStorage> {
+    let locations = Locations::resolve(command_line.user_dir.as_ref().map(String::as_str))
+        .expect("Failed to find user dir");
+    let storage = FileStorage::new(&locations.data, true).expect("Failed to open user dir");
+    let settings_storage =
+        FileStorage::new(&locations.config, true).expect("Failed to open config dir");
+    App::with_settings_storage(Frontend::Unix, storage, Some(settings_storage), seed)
+}
+
+fn scratch_app(dir: &Path, seed: usize) -> App<FileStorage> {
+    let storage = FileStorage::new(dir, true).expect("Failed to create scratch dir");
+    App::new(Frontend::Unix, storage, seed)
+}
+
+fn play(options: Options) {
+    let mut server = options.serve.map(|address| {
+        spectate::Server::bind(address.as_str()).expect("Failed to start spectator server")
+    });
+
+    let mut session = Session::new(&options.command_line, rand::thread_rng().gen())
+        .expect("Failed to read replay");
 
     let mut context = Context::new().unwrap();
 
-    let mut app = App::new(Frontend::Unix, storage, rand::thread_rng().gen());
+    let mut app = match session.scratch_dir() {
+        Some(dir) => scratch_app(dir, session.seed()),
+        None => app(&options.command_line, session.seed()),
+    };
+    session.start(&mut app, context.size());
 
     let mut view = AppView::new(context.size());
 
+    // the cast stays the size the terminal started at, drawn by its own view, so resizing
+    // the terminal doesn't change its layout
+    let mut cast = options.record_cast.map(|path| {
+        let size = context.size();
+        let file = File::create(path).expect("Failed to create cast file");
+        let cast = CastWriter::new(BufWriter::new(file), size).expect("Failed to write cast");
+        (cast, AppView::new(size))
+    });
+    let start = Instant::now();
+
     loop {
         view.set_size(context.size());
 
+        // the terminal is drawn last, since the editor maps clicks by where it was last drawn
+        if let Some((ref mut cast, ref mut cast_view)) = cast {
+            cast.frame(cast_view, &app, start.elapsed())
+                .expect("Failed to write cast");
+        }
         context.render(&mut view, &app).unwrap();
+        if let Some(ref mut server) = server {
+            server.broadcast(&app);
+        }
         thread::sleep(Duration::from_millis(TICK_MILLIS));
 
-        if let Some(control_flow) = app.tick(
-            context.drain_input().unwrap(),
-            Duration::from_millis(TICK_MILLIS),
-        ) {
+        let live_inputs = context.drain_input().unwrap().collect::<Vec<_>>();
+        let (inputs, period) =
+            match session.tick(live_inputs, Duration::from_millis(TICK_MILLIS)) {
+                Some(tick) => tick,
+                None => break,
+            };
+
+        if let Some(control_flow) = app.tick(inputs, period) {
             match control_flow {
                 ControlFlow::Quit => break,
             }
         }
     }
+
+    session.finish().expect("Failed to write replay");
+}
+
+fn watch(address: String, command_line: &CommandLine) {
+    let frames = spectate::connect(address.as_str()).expect("Failed to connect to game");
+
+    let mut context = Context::new().unwrap();
+
+    let mut app = app(command_line, rand::thread_rng().gen());
+    app.spectate(SpectatorFrame::Waiting);
+
+    let mut view = AppView::new(context.size());
+
+    loop {
+        view.set_size(context.size());
+
+        context.render(&mut view, &app).unwrap();
+        thread::sleep(Duration::from_millis(TICK_MILLIS));
+
+        for input in context.drain_input().unwrap() {
+            match input {
+                ProtottyInput::Char('q') | prototty_inputs::ESCAPE | prototty_inputs::ETX => {
+                    return
+                }
+                _ => (),
+            }
+        }
+
+        // only the latest frame is worth drawing
+        let mut latest = None;
+        loop {
+            match frames.try_recv() {
+                Ok(frame) => latest = Some(frame),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+        if let Some(frame) = latest {
+            if let Ok(frame) = serde_json::from_str(&frame) {
+                app.spectate(frame);
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut options = options();
+    match options.watch.take() {
+        Some(address) => watch(address, &options.command_line),
+        None => play(options),
+    }
 }
@@ -0,0 +1,106 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+use serde_json;
+use prototty::Storage;
+use punchcards_prototty::App;
+
+pub const DEFAULT_ADDRESS: &'static str = "127.0.0.1:7878";
+
+const WRITE_TIMEOUT_MILLIS: u64 = 100;
+const ACCEPT_MILLIS: u64 = 100;
+
+// sends the app's screen to every connected viewer, one json line per change
+pub struct Server {
+    frames: Sender<String>,
+    num_clients: Arc<AtomicUsize>,
+    last_frame: String,
+}
+
+fn accept(listener: &TcpListener, clients: &mut Vec<TcpStream>, frame: &str) {
+    while let Ok((mut stream, _)) = listener.accept() {
+        let ready = stream
+            .set_nonblocking(false)
+            .and_then(|()| {
+                stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MILLIS)))
+            })
+            .is_ok();
+        // new viewers need the current screen even if nothing changes
+        if ready && (frame.is_empty() || writeln!(stream, "{}", frame).is_ok()) {
+            clients.push(stream);
+        }
+    }
+}
+
+// viewers are written to on their own thread, so a slow one never holds up the game
+fn serve(listener: TcpListener, frames: Receiver<String>, num_clients: Arc<AtomicUsize>) {
+    let mut clients = Vec::new();
+    let mut frame = String::new();
+    loop {
+        match frames.recv_timeout(Duration::from_millis(ACCEPT_MILLIS)) {
+            Ok(next) => {
+                frame = next;
+                // viewers that can't keep up or have gone away are dropped
+                clients.retain(|mut client| writeln!(client, "{}", frame).is_ok());
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        accept(&listener, &mut clients, &frame);
+        num_clients.store(clients.len(), Ordering::Relaxed);
+    }
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let (frames, receiver) = mpsc::channel();
+        let num_clients = Arc::new(AtomicUsize::new(0));
+        let thread_num_clients = Arc::clone(&num_clients);
+        thread::spawn(move || serve(listener, receiver, thread_num_clients));
+        Ok(Self {
+            frames,
+            num_clients,
+            last_frame: String::new(),
+        })
+    }
+
+    pub fn broadcast<S: Storage>(&mut self, app: &App<S>) {
+        // the first viewer to connect is sent a fresh frame on the next broadcast
+        if self.num_clients.load(Ordering::Relaxed) == 0 {
+            self.last_frame.clear();
+            return;
+        }
+        let frame = serde_json::to_string(&app.spectator_frame())
+            .expect("Failed to serialize spectator frame");
+        if frame == self.last_frame {
+            return;
+        }
+        self.last_frame = frame.clone();
+        let _ = self.frames.send(frame);
+    }
+}
+
+// frames arrive on a separate thread so the viewer keeps responding to input
+pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Receiver<String>> {
+    let stream = TcpStream::connect(address)?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    Ok(receiver)
+}